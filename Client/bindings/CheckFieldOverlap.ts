@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A proposed boundary to check against the other fields of a farm.
+ */
+export type CheckFieldOverlap = { farm_id: number, map_polygon_string: string, 
+/**
+ * Field being redrawn, left out of the comparison.
+ */
+field_id: number | null, };
\ No newline at end of file
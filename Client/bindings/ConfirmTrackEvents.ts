@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CreateFieldEvent } from "./CreateFieldEvent";
+import type { CreateHarvestEvent } from "./CreateHarvestEvent";
+
+/**
+ * Drafts from a track preview as confirmed by the user.
+ */
+export type ConfirmTrackEvents = { field_events: Array<CreateFieldEvent>, harvest_events: Array<CreateHarvestEvent>, };
\ No newline at end of file
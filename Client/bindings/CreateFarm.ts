@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { OverlapPolicy } from "./OverlapPolicy";
+
+export type CreateFarm = { name: string, farm_coordinates: string, 
+/**
+ * `MM-DD`, defaults to January 1st.
+ */
+season_start?: string, field_overlap_policy?: OverlapPolicy, };
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type CreateFarmField = { name: string, map_polygon_string: string, farm_id: number, farm_field_group_id: number | null, };
\ No newline at end of file
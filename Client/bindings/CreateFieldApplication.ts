@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ApplicationKind } from "./ApplicationKind";
+import type { ApplicationUnit } from "./ApplicationUnit";
+
+/**
+ * Creates the field event and its application together.
+ */
+export type CreateFieldApplication = { time: string, field_id: number, type_id?: number, 
+/**
+ * See `CreateFieldEvent::event_name`.
+ */
+event_name?: string, description: string | null, kind: ApplicationKind, product: string, amount: number, unit: ApplicationUnit, n_kg_per_unit: number | null, p_kg_per_unit: number | null, k_kg_per_unit: number | null, area_ha: number | null, operator: string | null, weather: string | null, };
\ No newline at end of file
@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type CreateFieldEvent = { time: string, field_id: number, type_id?: number, 
+/**
+ * Name of the event type, matched ignoring case and created on the
+ * field's farm if missing. Used when `type_id` is not given.
+ */
+event_name?: string, description: string | null, };
\ No newline at end of file
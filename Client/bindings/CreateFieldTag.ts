@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type CreateFieldTag = { farm_id: number, name: string, category: string | null, draw_color: string | null, };
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { HarvestUnit } from "./HarvestUnit";
+
+export type CreateHarvestType = { name: string, farm_id: number, unit?: HarvestUnit, kg_per_unit: number | null, dry_matter_pct: number | null, n_removal_kg_per_unit: number | null, p_removal_kg_per_unit: number | null, k_removal_kg_per_unit: number | null, };
\ No newline at end of file
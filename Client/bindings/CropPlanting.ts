@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type CropPlanting = { id: number, field_id: number, crop: string, variety: string | null, sow_date: string, expected_harvest_date: string | null, terminate_date: string | null, version: number, created_at: string, created_by: string | null, updated_at: string, updated_by: string | null, };
\ No newline at end of file
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A proposed harvest, its amount has to be filled in before confirming.
+ */
+export type DraftHarvestEvent = { value: number | null, time: string, field_id: number, type_id: number, };
\ No newline at end of file
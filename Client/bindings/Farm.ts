@@ -1,3 +1,8 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { OverlapPolicy } from "./OverlapPolicy";
 
-export type Farm = { id: number, name: string, farm_coordinates: string, };
\ No newline at end of file
+export type Farm = { id: number, name: string, farm_coordinates: string, 
+/**
+ * `MM-DD` first day of the farm's farming season.
+ */
+season_start: string, field_overlap_policy: OverlapPolicy, created_at: string, created_by: string | null, updated_at: string, updated_by: string | null, };
\ No newline at end of file
@@ -1,3 +1,19 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type FarmField = { id: number, name: string, map_polygon_string: string, farm_id: number, farm_field_group_id: number | null, };
\ No newline at end of file
+export type FarmField = { id: number, name: string, 
+/**
+ * Boundary as a GeoJSON Feature in EPSG:3857, empty if the field has none.
+ */
+map_polygon_string: string, 
+/**
+ * Area of the boundary in square metres.
+ */
+area_m2: number | null, farm_id: number, farm_field_group_id: number | null, version: number, 
+/**
+ * Ids of the field's tags, see `/field_tags`.
+ */
+tags: Array<number>, 
+/**
+ * When the field was replaced by a split or merge, see `/farm_fields/{id}/lineage`.
+ */
+retired_at: string | null, created_at: string, created_by: string | null, updated_at: string, updated_by: string | null, };
\ No newline at end of file
@@ -1,3 +1,3 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type FarmFieldGroup = { id: number, name: string, farm_id: number, fields: Array<number>, draw_color: string, };
\ No newline at end of file
+export type FarmFieldGroup = { id: number, name: string, farm_id: number, fields: Array<number>, draw_color: string, version: number, created_at: string, created_by: string | null, updated_at: string, updated_by: string | null, };
\ No newline at end of file
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { FieldLineageLink } from "./FieldLineageLink";
+
+/**
+ * Direct predecessors and successors of a field.
+ */
+export type FarmFieldLineage = { field_id: number, 
+/**
+ * Fields this field was split or merged from.
+ */
+predecessors: Array<FieldLineageLink>, 
+/**
+ * Fields this field was split or merged into.
+ */
+successors: Array<FieldLineageLink>, };
\ No newline at end of file
@@ -0,0 +1,20 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ApplicationKind } from "./ApplicationKind";
+import type { ApplicationUnit } from "./ApplicationUnit";
+
+/**
+ * A field event with a structured record of what was spread or sprayed.
+ */
+export type FieldApplication = { 
+/**
+ * Id of the field event the application belongs to.
+ */
+id: number, time: string, field_id: number, type_id: number, event_name: string, description: string | null, kind: ApplicationKind, product: string, amount: number, unit: ApplicationUnit, 
+/**
+ * Kilograms of nitrogen per unit of product, 0.22 for 22-3-10 spread in kg.
+ */
+n_kg_per_unit: number | null, p_kg_per_unit: number | null, k_kg_per_unit: number | null, area_ha: number | null, operator: string | null, weather: string | null, 
+/**
+ * Version of the field event, bumped by changes to the application as well.
+ */
+version: number, created_at: string, created_by: string | null, updated_at: string, updated_by: string | null, };
\ No newline at end of file
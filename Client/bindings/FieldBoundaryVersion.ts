@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A boundary a field has had, kept when the field is redrawn.
+ */
+export type FieldBoundaryVersion = { id: number, field_id: number, 
+/**
+ * Boundary as a GeoJSON Feature in EPSG:3857.
+ */
+map_polygon_string: string, area_m2: number, valid_from: string, 
+/**
+ * `None` for the current boundary.
+ */
+valid_to: string | null, 
+/**
+ * Version of the field the boundary was drawn in.
+ */
+field_version: number, created_at: string, created_by: string | null, };
\ No newline at end of file
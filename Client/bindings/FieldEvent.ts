@@ -1,3 +1,7 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type FieldEvent = { id: number, time: string, field_id: number, event_name: string, description: string | null, };
\ No newline at end of file
+export type FieldEvent = { id: number, time: string, field_id: number, type_id: number, 
+/**
+ * Name of the event's type.
+ */
+event_name: string, description: string | null, version: number, created_at: string, created_by: string | null, updated_at: string, updated_by: string | null, };
\ No newline at end of file
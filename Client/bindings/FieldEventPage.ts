@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { FieldEvent } from "./FieldEvent";
+
+export type FieldEventPage = { events: Array<FieldEvent>, 
+/**
+ * Cursor for the next page, `None` when this is the last page.
+ */
+next_cursor: string | null, };
\ No newline at end of file
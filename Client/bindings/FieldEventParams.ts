@@ -0,0 +1,19 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type FieldEventParams = { 
+/**
+ * `next_cursor` from the previous page, omitted for the first page.
+ */
+cursor: string | null, limit: bigint | null, from: string | null, to: string | null, 
+/**
+ * Farming season, using the season start of each field's farm.
+ */
+season: number | null, field_id: number | null, group_id: number | null, 
+/**
+ * Only events on fields with this tag.
+ */
+tag_id: number | null, farm_id: number | null, type_id: number | null, 
+/**
+ * Case-insensitive substring match on the event type name.
+ */
+search: string | null, };
\ No newline at end of file
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type FieldEventType = { id: number, name: string, farm_id: number, 
+/**
+ * Archived types are kept for existing events but can't be used for new ones.
+ */
+archived: boolean, created_at: string, created_by: string | null, updated_at: string, updated_by: string | null, };
\ No newline at end of file
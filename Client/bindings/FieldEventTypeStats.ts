@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Number of events of one type, and of fields they were on.
+ */
+export type FieldEventTypeStats = { type_id: number, type_name: string, event_count: bigint, field_count: bigint, first_time: string, last_time: string, };
\ No newline at end of file
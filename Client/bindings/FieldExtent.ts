@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Bounding box of field boundaries in WGS84 degrees.
+ */
+export type FieldExtent = { min_lon: number, min_lat: number, max_lon: number, max_lat: number, };
\ No newline at end of file
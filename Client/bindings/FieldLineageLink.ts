@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { LineageOperation } from "./LineageOperation";
+
+/**
+ * A field another field was split or merged from, or into.
+ */
+export type FieldLineageLink = { field_id: number, field_name: string, operation: LineageOperation, retired_at: string | null, created_at: string, created_by: string | null, };
\ No newline at end of file
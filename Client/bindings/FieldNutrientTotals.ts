@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Nutrients applied to one field in one season. Applications without
+ * a given content don't add to that nutrient.
+ */
+export type FieldNutrientTotals = { field_id: number, field_name: string, season: number, season_label: string, applications: bigint, n_kg: number, p_kg: number, k_kg: number, };
\ No newline at end of file
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * An existing field overlapped by a boundary.
+ */
+export type FieldOverlap = { field_id: number, field_name: string, 
+/**
+ * Area of the intersection in square metres.
+ */
+overlap_m2: number, };
\ No newline at end of file
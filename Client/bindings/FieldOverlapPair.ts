@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Two fields of a farm whose boundaries overlap.
+ */
+export type FieldOverlapPair = { farm_id: number, field_id: number, field_name: string, other_field_id: number, other_field_name: string, overlap_m2: number, };
\ No newline at end of file
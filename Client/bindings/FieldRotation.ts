@@ -0,0 +1,9 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RotationSeason } from "./RotationSeason";
+import type { RotationWarning } from "./RotationWarning";
+
+export type FieldRotation = { field_id: number, field_name: string, 
+/**
+ * Seasons with plantings, oldest first.
+ */
+seasons: Array<RotationSeason>, warnings: Array<RotationWarning>, };
\ No newline at end of file
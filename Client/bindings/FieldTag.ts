@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A label on fields. Unlike groups a field can have any number of tags.
+ */
+export type FieldTag = { id: number, farm_id: number, name: string, 
+/**
+ * What the tag groups fields by, like soil type or ownership.
+ */
+category: string | null, draw_color: string | null, 
+/**
+ * Fields with the tag, leaving out retired fields.
+ */
+fields: Array<number>, version: number, created_at: string, created_by: string | null, updated_at: string, updated_by: string | null, };
\ No newline at end of file
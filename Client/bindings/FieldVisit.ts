@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Time a track spent inside a field.
+ */
+export type FieldVisit = { field_id: number, field_name: string, 
+/**
+ * First track point inside the field.
+ */
+start: string, 
+/**
+ * Last track point inside the field.
+ */
+end: string, 
+/**
+ * Time between consecutive points inside the field, without pauses.
+ */
+minutes: number, points: number, };
\ No newline at end of file
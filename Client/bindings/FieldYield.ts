@@ -0,0 +1,16 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Harvest of one type from a field in a season, relative to the field's area.
+ */
+export type FieldYield = { field_id: number, field_name: string, type_id: number, type_name: string, season: number, season_label: string, unit: string, total: number, 
+/**
+ * Area of the field at the season's last harvest, in hectares.
+ */
+area_ha: number | null, 
+/**
+ * Each harvest divided by the area of the boundary valid when it was made.
+ * Harvests from before the first recorded boundary use that boundary,
+ * fields without any boundary have no yield per hectare.
+ */
+yield_per_ha: number | null, };
\ No newline at end of file
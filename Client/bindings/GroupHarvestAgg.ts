@@ -1,3 +1,7 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type GroupHarvestAgg = { group_id: number, group_name: string, group_color: string, value: bigint, };
\ No newline at end of file
+export type GroupHarvestAgg = { group_id: number, group_name: string, group_color: string, 
+/**
+ * Unit of the total, one row per unit when summing in the harvest types' own units.
+ */
+unit: string, value: number, };
\ No newline at end of file
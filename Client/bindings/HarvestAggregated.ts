@@ -1,4 +1,16 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { HarvestTimeseries } from "./HarvestTimeseries";
 
-export type HarvestAggregated = { type_id: number, type_name: string, harvests: Array<HarvestTimeseries>, };
\ No newline at end of file
+/**
+ * Harvest totals per period for one harvest type,
+ * and for one field, group or farm unless grouping by harvest type.
+ */
+export type HarvestAggregated = { type_id: number, type_name: string, 
+/**
+ * Id of the field, group, tag or farm, `None` when grouping by harvest type or for ungrouped fields.
+ */
+key_id: number | null, key_name: string | null, 
+/**
+ * Unit of the totals, the harvest type's own unit unless another was requested.
+ */
+unit: string, harvests: Array<HarvestTimeseries>, };
\ No newline at end of file
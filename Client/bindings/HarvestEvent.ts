@@ -1,3 +1,3 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type HarvestEvent = { id: number, value: number, time: string, field_id: number, type_name: string, type_id: number, };
\ No newline at end of file
+export type HarvestEvent = { id: number, value: number, time: string, field_id: number, type_name: string, type_id: number, version: number, created_at: string, created_by: string | null, updated_at: string, updated_by: string | null, };
\ No newline at end of file
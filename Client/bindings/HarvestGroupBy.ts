@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type HarvestGroupBy = "harvest_type" | "field" | "group" | "tag" | "farm";
\ No newline at end of file
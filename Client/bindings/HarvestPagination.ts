@@ -2,4 +2,4 @@
 import type { HarvestEvent } from "./HarvestEvent";
 import type { HarvestParams } from "./HarvestParams";
 
-export type HarvestPagination = { params: HarvestParams, events: Array<HarvestEvent>, };
\ No newline at end of file
+export type HarvestPagination = { params: HarvestParams, events: Array<HarvestEvent>, total_count: bigint, page_count: bigint, };
\ No newline at end of file
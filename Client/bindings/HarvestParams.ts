@@ -1,3 +1,12 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { HarvestSort } from "./HarvestSort";
 
-export type HarvestParams = { year: number, page: number, page_size: number, };
\ No newline at end of file
+export type HarvestParams = { year: number | null, 
+/**
+ * Farming season, using the season start of each field's farm.
+ */
+season: number | null, from: string | null, to: string | null, page: number, page_size: number, field_id: number | null, group_id: number | null, 
+/**
+ * Only harvests on fields with this tag.
+ */
+tag_id: number | null, sort: HarvestSort, };
\ No newline at end of file
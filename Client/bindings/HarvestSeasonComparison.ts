@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SeasonCumulative } from "./SeasonCumulative";
+
+export type HarvestSeasonComparison = { type_id: number, type_name: string, 
+/**
+ * Id of the field or group, `None` when comparing per harvest type or for ungrouped fields.
+ */
+key_id: number | null, key_name: string | null, unit: string, 
+/**
+ * Compared seasons, most recent first.
+ */
+seasons: Array<SeasonCumulative>, };
\ No newline at end of file
@@ -1,3 +1,7 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type HarvestTimeseries = { date: string, total: bigint, };
\ No newline at end of file
+export type HarvestTimeseries = { 
+/**
+ * ISO formatted key of the period, see `Granularity::key`.
+ */
+date: string, total: number, };
\ No newline at end of file
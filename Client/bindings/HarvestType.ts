@@ -1,3 +1,20 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { HarvestUnit } from "./HarvestUnit";
 
-export type HarvestType = { id: number, name: string, };
\ No newline at end of file
+export type HarvestType = { id: number, name: string, farm_id: number, 
+/**
+ * Archived types are kept for existing harvests but can't be used for new ones.
+ */
+archived: boolean, unit: HarvestUnit, 
+/**
+ * Weight of one unit, needed to report units other than kg and tonne by mass.
+ */
+kg_per_unit: number | null, 
+/**
+ * Dry matter percentage, needed to report as dry matter.
+ */
+dry_matter_pct: number | null, 
+/**
+ * Kilograms of nitrogen removed from the field per unit harvested.
+ */
+n_removal_kg_per_unit: number | null, p_removal_kg_per_unit: number | null, k_removal_kg_per_unit: number | null, created_at: string, created_by: string | null, updated_at: string, updated_by: string | null, };
\ No newline at end of file
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ImportedField } from "./ImportedField";
+
+/**
+ * Previewed features to create as fields, possibly renamed or left out.
+ */
+export type ImportFarmFields = { farm_id: number, farm_field_group_id: number | null, fields: Array<ImportedField>, };
\ No newline at end of file
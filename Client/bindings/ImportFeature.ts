@@ -0,0 +1,28 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { FieldOverlap } from "./FieldOverlap";
+
+/**
+ * A feature of an import file as it would become a field.
+ */
+export type ImportFeature = { 
+/**
+ * Position of the feature in the file.
+ */
+index: number, name: string, 
+/**
+ * Attributes of the feature, from the `.dbf` or the KML's extended data.
+ */
+properties: { [key: string]: string }, 
+/**
+ * Boundary as a GeoJSON Feature in EPSG:3857, as accepted by `POST /farm_fields`.
+ */
+map_polygon_string: string, area_m2: number, 
+/**
+ * Existing fields of the farm the feature overlaps.
+ */
+overlaps: Array<FieldOverlap>, 
+/**
+ * The name is used by an active field or another feature of the file,
+ * the feature must be renamed to be imported.
+ */
+name_taken: boolean, };
\ No newline at end of file
@@ -0,0 +1,13 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ImportFeature } from "./ImportFeature";
+
+export type ImportPreview = { 
+/**
+ * Coordinate system the features were reprojected from,
+ * `None` when they were in WGS84 already.
+ */
+source_crs: string | null, features: Array<ImportFeature>, 
+/**
+ * Names of features without any polygon area, which can't become fields.
+ */
+skipped: Array<string>, };
\ No newline at end of file
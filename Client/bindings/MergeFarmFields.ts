@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Replaces fields of a farm by one field covering all of them.
+ * The merged fields are retired and keep their events and harvests.
+ */
+export type MergeFarmFields = { field_ids: Array<number>, name: string, farm_field_group_id: number | null, };
\ No newline at end of file
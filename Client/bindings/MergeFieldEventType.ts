@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Reassigns every event of this type to `into_type_id` and removes this type.
+ */
+export type MergeFieldEventType = { into_type_id: number, };
\ No newline at end of file
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type MergedFieldEventType = { type_id: number, 
+/**
+ * Number of field events moved to `type_id`.
+ */
+moved_events: bigint, };
\ No newline at end of file
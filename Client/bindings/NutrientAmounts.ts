@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type NutrientAmounts = { n_kg: number, p_kg: number, k_kg: number, };
\ No newline at end of file
@@ -0,0 +1,16 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { NutrientAmounts } from "./NutrientAmounts";
+
+/**
+ * Nutrients applied to a field in a season against those removed by its harvests.
+ */
+export type NutrientBalance = { field_id: number, field_name: string, season: number, season_label: string, applied: NutrientAmounts, removed: NutrientAmounts, 
+/**
+ * Applied minus removed, negative when the field is being depleted.
+ */
+balance: NutrientAmounts, 
+/**
+ * Harvest types in the season missing a removal coefficient, their
+ * harvests don't count towards the removed amount of that nutrient.
+ */
+missing_removal_types: Array<string>, };
\ No newline at end of file
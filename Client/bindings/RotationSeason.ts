@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CropPlanting } from "./CropPlanting";
+
+export type RotationSeason = { season: number, label: string, plantings: Array<CropPlanting>, };
\ No newline at end of file
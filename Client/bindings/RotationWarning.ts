@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A crop planted on a field in more consecutive seasons than allowed.
+ */
+export type RotationWarning = { field_id: number, field_name: string, crop: string, first_season: number, last_season: number, consecutive_seasons: number, };
\ No newline at end of file
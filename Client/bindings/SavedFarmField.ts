@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { FieldOverlap } from "./FieldOverlap";
+
+/**
+ * A created or updated field, with the fields its boundary overlaps when the
+ * farm only warns about overlaps.
+ */
+export type SavedFarmField = { id: number, version: number, overlaps: Array<FieldOverlap>, };
\ No newline at end of file
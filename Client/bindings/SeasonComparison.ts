@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { HarvestSeasonComparison } from "./HarvestSeasonComparison";
+
+export type SeasonComparison = { current_season: number, current_label: string, 
+/**
+ * The day of season every season is compared at.
+ */
+day_of_season: bigint, by_type: Array<HarvestSeasonComparison>, by_group: Array<HarvestSeasonComparison>, by_field: Array<HarvestSeasonComparison>, };
\ No newline at end of file
@@ -0,0 +1,16 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SeasonDayTotal } from "./SeasonDayTotal";
+
+export type SeasonCumulative = { season: number, label: string, 
+/**
+ * Cumulative total up to and including the compared day of season.
+ */
+total_to_date: number, 
+/**
+ * Total for the whole season.
+ */
+total: number, 
+/**
+ * Cumulative total on each day of the season with harvests.
+ */
+cumulative: Array<SeasonDayTotal>, };
\ No newline at end of file
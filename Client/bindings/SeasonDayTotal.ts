@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type SeasonDayTotal = { 
+/**
+ * Days since the start of the season, the first day being 0.
+ */
+day: bigint, total: number, };
\ No newline at end of file
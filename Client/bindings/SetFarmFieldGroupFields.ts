@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * The fields a group should have, fields not listed are taken out of the group.
+ */
+export type SetFarmFieldGroupFields = { fields: Array<number>, };
\ No newline at end of file
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * The active fields a tag should be on, it is removed from active fields not listed.
+ */
+export type SetFieldTagFields = { fields: Array<number>, };
\ No newline at end of file
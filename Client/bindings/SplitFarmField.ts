@@ -0,0 +1,20 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Replaces a field by new fields, cut either along `cut_line` or drawn as `polygons`.
+ * The field is retired and keeps its events and harvests.
+ */
+export type SplitFarmField = { 
+/**
+ * Names of the new fields, in the order of `polygons`,
+ * or from west to east for the parts cut by `cut_line`.
+ */
+names: Array<string>, 
+/**
+ * GeoJSON LineString in EPSG:3857 across the field's boundary.
+ */
+cut_line?: string, 
+/**
+ * GeoJSON boundaries of the new fields.
+ */
+polygons?: Array<string>, };
\ No newline at end of file
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type TagHarvestAgg = { tag_id: number, tag_name: string, tag_category: string | null, tag_color: string | null, 
+/**
+ * Unit of the total, one row per unit when summing in the harvest types' own units.
+ */
+unit: string, value: number, };
\ No newline at end of file
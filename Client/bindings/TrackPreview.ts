@@ -0,0 +1,22 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CreateFieldEvent } from "./CreateFieldEvent";
+import type { DraftHarvestEvent } from "./DraftHarvestEvent";
+import type { FieldVisit } from "./FieldVisit";
+
+export type TrackPreview = { start: string | null, end: string | null, points: number, 
+/**
+ * Points outside every field of the farm, like on roads and in the yard.
+ */
+points_outside: number, 
+/**
+ * Fields worked, in the order they were entered.
+ */
+visits: Array<FieldVisit>, 
+/**
+ * One per visit when an event type was given.
+ */
+field_events: Array<CreateFieldEvent>, 
+/**
+ * One per visit when a harvest type was given.
+ */
+harvest_events: Array<DraftHarvestEvent>, };
\ No newline at end of file
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * JSON merge patch for a planting, only the given members are changed.
+ */
+export type UpdateCropPlanting = { field_id?: number, crop?: string, variety?: string | null, sow_date?: string, expected_harvest_date?: string | null, terminate_date?: string | null, };
\ No newline at end of file
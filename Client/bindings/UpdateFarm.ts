@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { OverlapPolicy } from "./OverlapPolicy";
+
+/**
+ * JSON merge patch for a farm, only the given members are changed.
+ */
+export type UpdateFarm = { name?: string, farm_coordinates?: string, season_start?: string, field_overlap_policy?: OverlapPolicy, };
\ No newline at end of file
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * JSON merge patch for a field, only the given members are changed.
+ */
+export type UpdateFarmField = { name?: string, map_polygon_string?: string, farm_field_group_id?: number | null, };
\ No newline at end of file
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * JSON merge patch for a group, only the given members are changed.
+ */
+export type UpdateFarmFieldGroup = { name?: string, draw_color?: string, };
\ No newline at end of file
@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ApplicationKind } from "./ApplicationKind";
+import type { ApplicationUnit } from "./ApplicationUnit";
+
+/**
+ * JSON merge patch for the application, the event itself is changed through `/field_event`.
+ */
+export type UpdateFieldApplication = { kind?: ApplicationKind, product?: string, amount?: number, unit?: ApplicationUnit, n_kg_per_unit?: number | null, p_kg_per_unit?: number | null, k_kg_per_unit?: number | null, area_ha?: number | null, operator?: string | null, weather?: string | null, };
\ No newline at end of file
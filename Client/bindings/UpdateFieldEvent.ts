@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * JSON merge patch for a field event, only the given members are changed.
+ */
+export type UpdateFieldEvent = { time?: string, field_id?: number, type_id?: number, 
+/**
+ * See `CreateFieldEvent::event_name`.
+ */
+event_name?: string, description?: string | null, };
\ No newline at end of file
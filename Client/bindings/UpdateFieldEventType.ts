@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * JSON merge patch for a field event type, only the given members are changed.
+ */
+export type UpdateFieldEventType = { name?: string, archived?: boolean, };
\ No newline at end of file
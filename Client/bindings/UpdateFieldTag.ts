@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * JSON merge patch for a tag, only the given members are changed.
+ */
+export type UpdateFieldTag = { name?: string, category?: string | null, draw_color?: string | null, };
\ No newline at end of file
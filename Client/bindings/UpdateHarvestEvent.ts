@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * JSON merge patch for a harvest event, only the given members are changed.
+ */
+export type UpdateHarvestEvent = { value?: number, time?: string, type_id?: number, };
\ No newline at end of file
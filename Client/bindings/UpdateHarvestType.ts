@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { HarvestUnit } from "./HarvestUnit";
+
+/**
+ * JSON merge patch for a harvest type, only the given members are changed.
+ */
+export type UpdateHarvestType = { name?: string, archived?: boolean, 
+/**
+ * Can only be changed while the type has no harvests.
+ */
+unit?: HarvestUnit, kg_per_unit?: number | null, dry_matter_pct?: number | null, n_removal_kg_per_unit?: number | null, p_removal_kg_per_unit?: number | null, k_removal_kg_per_unit?: number | null, };
\ No newline at end of file
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * JSON merge patch for a user, only the given members are changed.
+ * An empty or null password leaves the current password unchanged.
+ */
+export type UpdateUser = { name?: string, email?: string, password?: string | null, };
\ No newline at end of file
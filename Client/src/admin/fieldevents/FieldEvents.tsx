@@ -1,5 +1,5 @@
 import { createMemo, createSignal, For, Show } from "solid-js";
-import { createQuery, useQueryClient } from "@tanstack/solid-query";
+import { createInfiniteQuery, createQuery, useQueryClient } from "@tanstack/solid-query";
 import {
   Button,
   Dialog,
@@ -23,7 +23,10 @@ import { Delete, Edit } from "@suid/icons-material";
 import DatePicker, { PickerValue } from "@rnwonder/solid-date-picker";
 import "@rnwonder/solid-date-picker/dist/style.css";
 
+import { CreateFieldEvent } from "../../../bindings/CreateFieldEvent";
 import { FieldEvent } from "../../../bindings/FieldEvent";
+import { FieldEventPage } from "../../../bindings/FieldEventPage";
+import { UpdateFieldEvent } from "../../../bindings/UpdateFieldEvent";
 import { FarmFieldGroupMeta } from "../../../bindings/FarmFieldGroupMeta";
 import { ConfirmDeleteDialog, formatDate } from "../../Utils";
 import { prepareAuth } from "../../requests";
@@ -38,9 +41,16 @@ type EventForm = {
   time: string;
 };
 
-async function getFieldEvents() {
-  const response = await fetch("/api/field_event");
-  return response.json() as Promise<FieldEvent[]>;
+const pageSize = 50;
+
+async function getFieldEvents(cursor: string | null) {
+  const url = new URL(`${document.location.origin}/api/field_event`);
+  url.searchParams.set("limit", pageSize.toString());
+  if (cursor) {
+    url.searchParams.set("cursor", cursor);
+  }
+  const response = await fetch(url);
+  return (await response.json()) as FieldEventPage;
 }
 
 async function saveFieldEvent(form: EventForm) {
@@ -49,8 +59,7 @@ async function saveFieldEvent(form: EventForm) {
     return false;
   }
 
-  const payload: FieldEvent = {
-    id: form.id,
+  const payload: CreateFieldEvent | UpdateFieldEvent = {
     field_id: form.field_id,
     event_name: form.event_name.trim(),
     description: form.description.trim() ? form.description.trim() : null,
@@ -102,11 +111,17 @@ export default function FieldEvents() {
       ),
   }));
 
-  const fieldEvents = createQuery<FieldEvent[]>(() => ({
+  const fieldEvents = createInfiniteQuery(() => ({
     queryKey: ["admin_field_events"],
-    queryFn: getFieldEvents,
+    queryFn: ({ pageParam }) => getFieldEvents(pageParam),
+    initialPageParam: null as string | null,
+    getNextPageParam: (lastPage: FieldEventPage) => lastPage.next_cursor ?? undefined,
   }));
 
+  const events = createMemo(
+    () => fieldEvents.data?.pages.flatMap((page) => page.events) ?? [],
+  );
+
   const fieldLookup = createMemo(() => {
     const map = new Map<number, { fieldName: string; groupName: string }>();
     groups.data?.forEach((group) => {
@@ -360,7 +375,7 @@ export default function FieldEvents() {
                   </TableRow>
                 </TableHead>
                 <TableBody>
-                  <For each={fieldEvents.data}>
+                  <For each={events()}>
                     {(event) => (
                       <TableRow
                         class={`${styles.row} ${styles.clickableRow}`}
@@ -401,7 +416,7 @@ export default function FieldEvents() {
           </div>
 
           <div class={styles.mobileCards}>
-            <For each={fieldEvents.data}>
+            <For each={events()}>
               {(event) => (
                 <article
                   class={styles.mobileCard}
@@ -449,6 +464,18 @@ export default function FieldEvents() {
             </For>
           </div>
         </>
+
+        <Show when={fieldEvents.hasNextPage}>
+          <div style={{ "margin-top": "12px" }}>
+            <Button
+              variant="outlined"
+              disabled={fieldEvents.isFetchingNextPage}
+              onClick={() => fieldEvents.fetchNextPage()}
+            >
+              Load more
+            </Button>
+          </div>
+        </Show>
       </Show>
     </main>
   );
@@ -1,13 +1,20 @@
 import { createQuery } from "@tanstack/solid-query";
-import { createSignal, For, Show } from "solid-js";
+import { createMemo, createSignal, For, Show } from "solid-js";
+import { CreateHarvestType } from "../../../bindings/CreateHarvestType";
+import { Farm } from "../../../bindings/Farm";
 import { HarvestType } from "../../../bindings/HarvestType";
+import { UpdateHarvestType } from "../../../bindings/UpdateHarvestType";
 import {
   Button,
   Dialog,
   DialogActions,
   DialogContent,
   DialogTitle,
+  FormControl,
   IconButton,
+  InputLabel,
+  MenuItem,
+  Select,
   Table,
   TableBody,
   TableCell,
@@ -17,43 +24,69 @@ import {
   TextField,
 } from "@suid/material";
 import { Edit } from "@suid/icons-material";
-import { prepareAuth } from "../../requests";
+import { get_farm, prepareAuth } from "../../requests";
 import styles from "../AdminSurface.module.css";
 
+type TypeForm = {
+  id: number;
+  name: string;
+};
+
 // apicalls dont return a new object, using refetch instead
-const updateHarvestType = (harvestType: HarvestType) => {
+const updateHarvestType = (harvestType: TypeForm) => {
   const authHeaders = prepareAuth(true);
   if (authHeaders === null) {
     console.log("not allowed to post without bearer token");
     return null;
   }
+  const payload: UpdateHarvestType = { name: harvestType.name };
   return fetch(`/api/harvest_type/${harvestType.id}`, {
     method: "PATCH",
-    body: JSON.stringify(harvestType),
+    body: JSON.stringify(payload),
     headers: authHeaders
   });
 };
 
-const createHarvestType = (harvestType: HarvestType) => {
+const createHarvestType = (harvestType: TypeForm, farmId: number) => {
   const authHeaders = prepareAuth(true);
   if (authHeaders === null) {
     console.log("not allowed to post without bearer token");
     return null;
   }
+  const payload: CreateHarvestType = {
+    name: harvestType.name,
+    farm_id: farmId,
+    kg_per_unit: null,
+    dry_matter_pct: null,
+    n_removal_kg_per_unit: null,
+    p_removal_kg_per_unit: null,
+    k_removal_kg_per_unit: null,
+  };
   return fetch(`/api/harvest_type`, {
     method: "POST",
-    body: JSON.stringify(harvestType),
+    body: JSON.stringify(payload),
     headers: authHeaders
   });
 };
 
 export default function HarvestTypes() {
-  const [addForm, setAddForm] = createSignal<HarvestType | undefined>(undefined);
-  const [editForm, setEditForm] = createSignal<HarvestType | undefined>(undefined);
+  const [addForm, setAddForm] = createSignal<TypeForm | undefined>(undefined);
+  const [editForm, setEditForm] = createSignal<TypeForm | undefined>(undefined);
+  const [selectedFarm, setSelectedFarm] = createSignal<number | undefined>(undefined);
+
+  const farms = createQuery<Farm[]>(() => ({
+    queryKey: ["farms"],
+    queryFn: get_farm,
+  }));
+
+  // the first farm until another is picked
+  const farmId = createMemo(() => selectedFarm() ?? farms.data?.[0]?.id);
 
   const harvestTypes = createQuery<HarvestType[]>(() => ({
-    queryKey: ["harvest_types"],
-    queryFn: () => fetch("/api/harvest_type").then((a) => a.json()),
+    queryKey: ["harvest_types", farmId()],
+    queryFn: () =>
+      fetch(`/api/harvest_type?farm_id=${farmId()}`).then((a) => a.json()),
+    enabled: farmId() !== undefined,
   }));
 
   return (
@@ -99,7 +132,7 @@ export default function HarvestTypes() {
               <Button
                 variant="contained"
                 onClick={async () => {
-                  const n = await createHarvestType(toAdd());
+                  const n = await createHarvestType(toAdd(), farmId()!);
                   if (n?.ok) {
                     setAddForm(undefined);
                     harvestTypes.refetch();
@@ -131,10 +164,26 @@ export default function HarvestTypes() {
           <p class={styles.eyebrow}>Admin editor</p>
           <h2>Harvest types</h2>
         </div>
+        <Show when={(farms.data?.length ?? 0) > 1}>
+          <FormControl size="small">
+            <InputLabel id="admin-harvest-type-farm">Farm</InputLabel>
+            <Select
+              labelId="admin-harvest-type-farm"
+              label="Farm"
+              value={farmId() ?? ""}
+              onChange={(event) => setSelectedFarm(Number(event.target.value))}
+            >
+              <For each={farms.data}>
+                {(farm) => <MenuItem value={farm.id}>{farm.name}</MenuItem>}
+              </For>
+            </Select>
+          </FormControl>
+        </Show>
         <Button
           class={styles.heroAction}
           size="small"
           variant="contained"
+          disabled={farmId() === undefined}
           onClick={() => setAddForm({ id: -1, name: "" })}
         >
           New type
@@ -10,7 +10,7 @@ import { Accessor, createMemo, createSignal } from "solid-js";
 import DatePicker, { PickerValue } from "@rnwonder/solid-date-picker";
 import "@rnwonder/solid-date-picker/dist/style.css";
 
-import { FieldEvent } from "../../bindings/FieldEvent";
+import { CreateFieldEvent } from "../../bindings/CreateFieldEvent";
 import { prepareAuth } from "../requests";
 import styles from "./FieldEventForm.module.css";
 
@@ -19,14 +19,13 @@ async function createFieldEvent(
   eventName: string,
   time: string,
   description: string,
-): Promise<FieldEvent> {
+): Promise<number> {
   const authHeaders = prepareAuth(true);
   if (authHeaders === null) {
     throw new Error("not allowed to post without bearer token");
   }
 
-  const payload: FieldEvent = {
-    id: -1,
+  const payload: CreateFieldEvent = {
     field_id: fieldId,
     event_name: eventName,
     time: new Date(time).toISOString(),
@@ -44,17 +43,14 @@ async function createFieldEvent(
   }
 
   const id = await response.json();
-  return {
-    ...payload,
-    id: Number(id),
-  };
+  return Number(id);
 }
 
 export function FieldEventForm(props: {
   isOpen: Accessor<boolean>;
   fieldId: number;
   onClose: () => void;
-  onCreated: (event: FieldEvent) => void;
+  onCreated: (eventId: number) => void;
 }) {
   const [eventName, setEventName] = createSignal("");
   const [description, setDescription] = createSignal("");
@@ -2,6 +2,7 @@ import { jwt_token } from "./App";
 import { Farm } from "../bindings/Farm";
 import { FarmField } from "../bindings/FarmField";
 import { FarmFieldGroup } from "../bindings/FarmFieldGroup";
+import { SavedFarmField } from "../bindings/SavedFarmField";
 
 export function prepareAuth(isPost: boolean = false): Headers | null {
   const token = jwt_token();
@@ -65,7 +66,7 @@ export async function tryPatchNewFieldGroup(
 ): Promise<number | undefined> {
   const authHeaders = prepareAuth(true);
   if (authHeaders) {
-    return fetch("/api/farm_field_groups", {
+    return fetch(`/api/farm_field_groups/${f.id}`, {
       method: "PATCH",
       headers: authHeaders,
       body: JSON.stringify(f),
@@ -77,7 +78,7 @@ export async function tryPatchNewFieldGroup(
 
 export async function tryPostNewField(
   f: FarmField,
-): Promise<number | undefined> {
+): Promise<SavedFarmField | undefined> {
   const authHeaders = prepareAuth(true);
   console.log("posting json: ", f);
   if (f.map_polygon_string.length == 0) {
@@ -42,7 +42,7 @@ export default function BalesPerAreaChart() {
           const y = area ? Number(agg.value) / area : 0;
 
           return ({
-            x: agg.group_name,
+            x: `${agg.group_name} (${agg.unit})`,
             y: y.toPrecision(2),
             fillColor: rgbToHex(agg.group_color),
           })
@@ -16,7 +16,7 @@ export default function GroupHarvestChart() {
       {
         name: "Bales",        
         data: harvestsByYear()!.map((agg) => ({
-          x: agg.group_name,
+          x: `${agg.group_name} (${agg.unit})`,
           y: agg.value,
           fillColor: rgbToHex(agg.group_color),
         })),
@@ -0,0 +1,76 @@
+use crate::errors::SorjordetError;
+
+use super::patch::Patch;
+use super::validation::validate_name;
+
+/// Length of the `name` column of the per-farm type catalogues,
+/// harvest types and field event types.
+const NAME_MAX_LEN: usize = 128;
+
+pub fn validate_type_name(name: &str) -> Result<(), SorjordetError> {
+    validate_name("name", name, NAME_MAX_LEN)
+}
+
+/// Checks the members of a type's merge patch every catalogue has.
+pub fn validate_type_patch(
+    name: &Patch<String>,
+    archived: &Patch<bool>,
+) -> Result<(), SorjordetError> {
+    name.reject_null("name")?;
+    archived.reject_null("archived")?;
+    if let Some(name) = name.value() {
+        validate_type_name(name)?;
+    }
+    Ok(())
+}
+
+/// Maps unique violations on a farm and name index to a conflict,
+/// `what` naming the entry like "harvest type".
+pub fn name_conflict(what: &'static str) -> impl Fn(sqlx::Error) -> SorjordetError {
+    move |err| match &err {
+        sqlx::Error::Database(db) if db.is_unique_violation() => SorjordetError::Conflict(format!(
+            "a {what} with this name already exists on the farm"
+        )),
+        _ => err.into(),
+    }
+}
+
+/// Maps errors inserting an entry on `farm_id`, which may not exist, like `name_conflict`.
+pub fn insert_error(what: &'static str, farm_id: i32) -> impl Fn(sqlx::Error) -> SorjordetError {
+    move |err| match &err {
+        sqlx::Error::Database(db) if db.is_foreign_key_violation() => {
+            SorjordetError::InvalidInput(format!("farm with id {farm_id} not found"))
+        }
+        _ => name_conflict(what)(err),
+    }
+}
+
+/// Checks that a type can be merged into another, given the farms of the
+/// two types as found, locked, in the catalogue.
+pub fn check_merge(
+    what: &str,
+    type_id: i32,
+    into_type_id: i32,
+    from_farm_id: Option<i32>,
+    into_farm_id: Option<i32>,
+) -> Result<(), SorjordetError> {
+    if type_id == into_type_id {
+        return Err(SorjordetError::InvalidInput(format!(
+            "a {what} can not be merged into itself"
+        )));
+    }
+    let (Some(from_farm_id), Some(into_farm_id)) = (from_farm_id, into_farm_id) else {
+        return Err(SorjordetError::NotFound(format!(
+            "{} with id {} or {} not found",
+            what.replace(' ', "_"),
+            type_id,
+            into_type_id
+        )));
+    };
+    if from_farm_id != into_farm_id {
+        return Err(SorjordetError::InvalidInput(format!(
+            "{what}s on different farms can not be merged"
+        )));
+    }
+    Ok(())
+}
@@ -0,0 +1,532 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use axum::{
+    self, Json, Router,
+    extract::{self, State},
+    response::IntoResponse,
+    routing::get,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool, query, query_as, query_scalar};
+use ts_rs::TS;
+
+use crate::auth::Claims;
+use crate::errors::SorjordetError;
+
+use super::etag::{IfMatch, etag, not_found_or_conflict};
+use super::farm_field::ensure_active_field;
+use super::patch::Patch;
+use super::period::SeasonStart;
+use super::validation::{Validate, ValidatedJson, validate_length, validate_name};
+
+#[derive(Serialize, Deserialize, FromRow, TS, Clone)]
+#[ts(export)]
+pub struct CropPlanting {
+    pub id: i32,
+    pub field_id: i32,
+    pub crop: String,
+    pub variety: Option<String>,
+    pub sow_date: NaiveDate,
+    pub expected_harvest_date: Option<NaiveDate>,
+    pub terminate_date: Option<NaiveDate>,
+    pub version: i32,
+    pub created_at: DateTime<Utc>,
+    pub created_by: Option<String>,
+    pub updated_at: DateTime<Utc>,
+    pub updated_by: Option<String>,
+}
+
+#[derive(Deserialize, TS)]
+#[ts(export)]
+pub struct CreateCropPlanting {
+    pub field_id: i32,
+    pub crop: String,
+    pub variety: Option<String>,
+    pub sow_date: NaiveDate,
+    pub expected_harvest_date: Option<NaiveDate>,
+    pub terminate_date: Option<NaiveDate>,
+}
+
+impl Validate for CreateCropPlanting {
+    fn validate(&self) -> Result<(), SorjordetError> {
+        validate_name("crop", &self.crop, 128)?;
+        validate_length("variety", self.variety.as_deref().unwrap_or_default(), 128)?;
+        for (field, date) in [
+            ("expected_harvest_date", self.expected_harvest_date),
+            ("terminate_date", self.terminate_date),
+        ] {
+            if date.is_some_and(|x| x < self.sow_date) {
+                return Err(SorjordetError::InvalidInput(format!(
+                    "{field} must not be before sow_date"
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// JSON merge patch for a planting, only the given members are changed.
+#[derive(Deserialize, TS)]
+#[ts(export)]
+pub struct UpdateCropPlanting {
+    #[serde(default)]
+    #[ts(as = "Option<i32>", optional)]
+    pub field_id: Patch<i32>,
+    #[serde(default)]
+    #[ts(as = "Option<String>", optional)]
+    pub crop: Patch<String>,
+    #[serde(default)]
+    #[ts(as = "Option<String>", optional = nullable)]
+    pub variety: Patch<String>,
+    #[serde(default)]
+    #[ts(as = "Option<NaiveDate>", optional)]
+    pub sow_date: Patch<NaiveDate>,
+    #[serde(default)]
+    #[ts(as = "Option<NaiveDate>", optional = nullable)]
+    pub expected_harvest_date: Patch<NaiveDate>,
+    #[serde(default)]
+    #[ts(as = "Option<NaiveDate>", optional = nullable)]
+    pub terminate_date: Patch<NaiveDate>,
+}
+
+impl Validate for UpdateCropPlanting {
+    fn validate(&self) -> Result<(), SorjordetError> {
+        self.field_id.reject_null("field_id")?;
+        self.crop.reject_null("crop")?;
+        self.sow_date.reject_null("sow_date")?;
+        if let Some(crop) = self.crop.value() {
+            validate_name("crop", crop, 128)?;
+        }
+        if let Some(variety) = self.variety.value() {
+            validate_length("variety", variety, 128)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+struct CropPlantingParams {
+    field_id: Option<i32>,
+    farm_id: Option<i32>,
+    /// Farming season of the sow date, using the season start of each field's farm.
+    season: Option<i32>,
+}
+
+#[derive(Deserialize)]
+struct RotationParams {
+    /// Most seasons in a row a crop may be planted on a field before it is warned about.
+    max_consecutive: Option<u32>,
+    farm_id: Option<i32>,
+    /// Also count the plantings on the fields a field was split or merged from, on by default.
+    include_predecessors: Option<bool>,
+}
+
+impl RotationParams {
+    fn max_consecutive(&self) -> Result<u32, SorjordetError> {
+        match self.max_consecutive.unwrap_or(DEFAULT_MAX_CONSECUTIVE) {
+            0 => Err(SorjordetError::InvalidInput(
+                "max_consecutive must be at least 1".to_string(),
+            )),
+            x => Ok(x),
+        }
+    }
+
+    fn include_predecessors(&self) -> bool {
+        self.include_predecessors.unwrap_or(true)
+    }
+}
+
+const DEFAULT_MAX_CONSECUTIVE: u32 = 3;
+
+#[derive(Serialize, TS)]
+#[ts(export)]
+pub struct RotationSeason {
+    season: i32,
+    label: String,
+    plantings: Vec<CropPlanting>,
+}
+
+/// A crop planted on a field in more consecutive seasons than allowed.
+#[derive(Serialize, TS)]
+#[ts(export)]
+pub struct RotationWarning {
+    field_id: i32,
+    field_name: String,
+    crop: String,
+    first_season: i32,
+    last_season: i32,
+    consecutive_seasons: u32,
+}
+
+#[derive(Serialize, TS)]
+#[ts(export)]
+pub struct FieldRotation {
+    field_id: i32,
+    field_name: String,
+    /// Seasons with plantings, oldest first.
+    seasons: Vec<RotationSeason>,
+    warnings: Vec<RotationWarning>,
+}
+
+/// Check violations are dates out of order after a partial update.
+fn dates_out_of_order(err: sqlx::Error) -> SorjordetError {
+    match &err {
+        sqlx::Error::Database(db) if db.is_check_violation() => SorjordetError::InvalidInput(
+            "expected_harvest_date and terminate_date must not be before sow_date".to_string(),
+        ),
+        _ => err.into(),
+    }
+}
+
+/// A planting in the rotation of a field, which is the field it was planted on
+/// or a field that replaced that one.
+struct SeasonPlanting {
+    field_id: i32,
+    field_name: String,
+    season: i32,
+    season_start: SeasonStart,
+    planting: CropPlanting,
+}
+
+/// Plantings with the farming season of their sow date, by field and sow date.
+/// Without `field_id` only active fields are included, and with `include_predecessors`
+/// every field also gets the plantings of the fields it was split or merged from.
+async fn plantings_by_season(
+    pool: &PgPool,
+    field_id: Option<i32>,
+    farm_id: Option<i32>,
+    include_predecessors: bool,
+) -> Result<Vec<SeasonPlanting>, SorjordetError> {
+    let rows = query!(
+        r#"SELECT p.id, p.field_id, p.crop, p.variety, p.sow_date, p.expected_harvest_date,
+                    p.terminate_date, p.version, p.created_at, p.created_by, p.updated_at,
+                    p.updated_by, f.id as rotation_field_id, f.name as field_name,
+                    fa.season_start_month, fa.season_start_day
+                FROM farm_field AS f
+                    CROSS JOIN LATERAL (
+                        SELECT h.field_id FROM field_with_predecessors(f.id) h WHERE $3
+                        UNION
+                        SELECT f.id
+                    ) AS h
+                    JOIN crop_planting AS p ON p.field_id = h.field_id
+                    JOIN farm AS fa ON fa.id = f.farm_id
+                WHERE ($1::int IS NULL OR f.id = $1)
+                    AND ($1::int IS NOT NULL OR f.retired_at IS NULL)
+                    AND ($2::int IS NULL OR f.farm_id = $2)
+                ORDER BY f.name, f.id, p.sow_date, p.id
+            "#,
+        field_id,
+        farm_id,
+        include_predecessors
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|x| {
+            let season_start =
+                SeasonStart::new(x.season_start_month as u32, x.season_start_day as u32)
+                    .unwrap_or_default();
+            SeasonPlanting {
+                field_id: x.rotation_field_id,
+                field_name: x.field_name,
+                season: season_start.season_of(x.sow_date),
+                season_start,
+                planting: CropPlanting {
+                    id: x.id,
+                    field_id: x.field_id,
+                    crop: x.crop,
+                    variety: x.variety,
+                    sow_date: x.sow_date,
+                    expected_harvest_date: x.expected_harvest_date,
+                    terminate_date: x.terminate_date,
+                    version: x.version,
+                    created_at: x.created_at,
+                    created_by: x.created_by,
+                    updated_at: x.updated_at,
+                    updated_by: x.updated_by,
+                },
+            }
+        })
+        .collect())
+}
+
+/// Runs of more than `max_consecutive` seasons in a row with the same crop on
+/// one field. Crops are compared ignoring case, a season without it ends the run.
+fn rotation_warnings(
+    field_id: i32,
+    field_name: &str,
+    seasons: &BTreeMap<i32, Vec<CropPlanting>>,
+    max_consecutive: u32,
+) -> Vec<RotationWarning> {
+    let mut crops = BTreeMap::<String, (String, BTreeSet<i32>)>::new();
+    for (season, plantings) in seasons {
+        for planting in plantings {
+            crops
+                .entry(planting.crop.trim().to_lowercase())
+                .or_insert_with(|| (planting.crop.trim().to_string(), BTreeSet::new()))
+                .1
+                .insert(*season);
+        }
+    }
+
+    let mut warnings = vec![];
+    for (crop, crop_seasons) in crops.into_values() {
+        let mut runs: Vec<(i32, i32)> = vec![];
+        for season in crop_seasons {
+            match runs.last_mut() {
+                Some((_, last)) if *last + 1 == season => *last = season,
+                _ => runs.push((season, season)),
+            }
+        }
+        for (first, last) in runs {
+            let consecutive_seasons = (last - first + 1) as u32;
+            if consecutive_seasons > max_consecutive {
+                warnings.push(RotationWarning {
+                    field_id,
+                    field_name: field_name.to_string(),
+                    crop: crop.clone(),
+                    first_season: first,
+                    last_season: last,
+                    consecutive_seasons,
+                });
+            }
+        }
+    }
+    warnings
+}
+
+async fn get_plantings(
+    State(pool): State<PgPool>,
+    extract::Query(params): extract::Query<CropPlantingParams>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    let result: Vec<CropPlanting> = query_as!(
+        CropPlanting,
+        "SELECT p.id, p.field_id, p.crop, p.variety, p.sow_date, p.expected_harvest_date,
+                    p.terminate_date, p.version, p.created_at, p.created_by, p.updated_at,
+                    p.updated_by
+                FROM crop_planting AS p
+                    JOIN farm_field AS f ON f.id = p.field_id
+                    JOIN farm AS fa ON fa.id = f.farm_id
+                WHERE ($1::int IS NULL OR p.field_id = $1)
+                    AND ($2::int IS NULL OR f.farm_id = $2)
+                    AND ($3::int IS NULL OR farm_season(p.sow_date::timestamp AT TIME ZONE 'UTC',
+                        fa.season_start_month, fa.season_start_day) = $3)
+                ORDER BY p.sow_date DESC, p.id DESC
+            ",
+        params.field_id,
+        params.farm_id,
+        params.season
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(result))
+}
+
+async fn get_field_rotation(
+    State(pool): State<PgPool>,
+    extract::Path(field_id): extract::Path<i32>,
+    extract::Query(params): extract::Query<RotationParams>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    let max_consecutive = params.max_consecutive()?;
+
+    let Some(field_name) = query_scalar!("SELECT name FROM farm_field WHERE id = $1", field_id)
+        .fetch_optional(&pool)
+        .await?
+    else {
+        return Err(SorjordetError::NotFound(format!(
+            "field with id {} not found",
+            field_id
+        )));
+    };
+
+    let plantings =
+        plantings_by_season(&pool, Some(field_id), None, params.include_predecessors()).await?;
+    let season_start = plantings
+        .first()
+        .map(|x| x.season_start)
+        .unwrap_or_default();
+
+    let mut seasons = BTreeMap::<i32, Vec<CropPlanting>>::new();
+    for x in plantings {
+        seasons.entry(x.season).or_default().push(x.planting);
+    }
+
+    Ok(Json(FieldRotation {
+        field_id,
+        warnings: rotation_warnings(field_id, &field_name, &seasons, max_consecutive),
+        field_name,
+        seasons: seasons
+            .into_iter()
+            .map(|(season, plantings)| RotationSeason {
+                season,
+                label: season_start.label(season),
+                plantings,
+            })
+            .collect(),
+    }))
+}
+
+async fn get_rotation_warnings(
+    State(pool): State<PgPool>,
+    extract::Query(params): extract::Query<RotationParams>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    let max_consecutive = params.max_consecutive()?;
+
+    // rows are ordered by field, so each field's plantings are together
+    let mut fields = Vec::<(i32, String, BTreeMap<i32, Vec<CropPlanting>>)>::new();
+    for x in plantings_by_season(&pool, None, params.farm_id, params.include_predecessors()).await?
+    {
+        if fields.last().is_none_or(|(id, _, _)| *id != x.field_id) {
+            fields.push((x.field_id, x.field_name, BTreeMap::new()));
+        }
+        if let Some((_, _, seasons)) = fields.last_mut() {
+            seasons.entry(x.season).or_default().push(x.planting);
+        }
+    }
+
+    let warnings: Vec<RotationWarning> = fields
+        .iter()
+        .flat_map(|(field_id, field_name, seasons)| {
+            rotation_warnings(*field_id, field_name, seasons, max_consecutive)
+        })
+        .collect();
+
+    Ok(Json(warnings))
+}
+
+async fn post_planting(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    ValidatedJson(payload): ValidatedJson<CreateCropPlanting>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    ensure_active_field(&pool, payload.field_id).await?;
+
+    let result = query_scalar!(
+        "INSERT INTO crop_planting
+                    (field_id, crop, variety, sow_date, expected_harvest_date, terminate_date,
+                    created_by, updated_by)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $7)
+                RETURNING id
+            ",
+        payload.field_id,
+        payload.crop.trim(),
+        payload.variety,
+        payload.sow_date,
+        payload.expected_harvest_date,
+        payload.terminate_date,
+        &claims.sub
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    tracing::info!("new crop_planting inserted by {}", claims.sub);
+
+    Ok(Json(result))
+}
+
+async fn patch_planting(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    extract::Path(planting_id): extract::Path<i32>,
+    IfMatch(expected_version): IfMatch,
+    ValidatedJson(payload): ValidatedJson<UpdateCropPlanting>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    if let Some(field_id) = payload.field_id.value() {
+        ensure_active_field(&pool, *field_id).await?;
+    }
+
+    let result = query_scalar!(
+        "UPDATE crop_planting
+                SET field_id = COALESCE($1, field_id),
+                    crop = COALESCE($2, crop),
+                    variety = CASE WHEN $3 THEN $4 ELSE variety END,
+                    sow_date = COALESCE($5, sow_date),
+                    expected_harvest_date =
+                        CASE WHEN $6 THEN $7 ELSE expected_harvest_date END,
+                    terminate_date = CASE WHEN $8 THEN $9 ELSE terminate_date END,
+                    version = version + 1,
+                    updated_at = NOW(),
+                    updated_by = $12
+                WHERE id = $10 AND ($11::int IS NULL OR version = $11)
+                RETURNING version
+            ",
+        payload.field_id.value(),
+        payload.crop.value().map(|x| x.trim()),
+        payload.variety.is_set(),
+        payload.variety.value(),
+        payload.sow_date.value(),
+        payload.expected_harvest_date.is_set(),
+        payload.expected_harvest_date.value(),
+        payload.terminate_date.is_set(),
+        payload.terminate_date.value(),
+        planting_id,
+        expected_version,
+        &claims.sub
+    )
+    .fetch_optional(&pool)
+    .await
+    .map_err(dates_out_of_order)?;
+
+    let Some(version) = result else {
+        return Err(not_found_or_conflict(
+            current_version(&pool, planting_id).await?,
+            format!("crop_planting with id {} not found", planting_id),
+        ));
+    };
+
+    tracing::info!("crop_planting {planting_id} updated by {}", claims.sub);
+
+    Ok(etag(version))
+}
+
+async fn delete_planting(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    extract::Path(planting_id): extract::Path<i32>,
+    IfMatch(expected_version): IfMatch,
+) -> Result<impl IntoResponse, SorjordetError> {
+    let result = query!(
+        "DELETE FROM crop_planting WHERE id = $1 AND ($2::int IS NULL OR version = $2)",
+        planting_id,
+        expected_version
+    )
+    .execute(&pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(not_found_or_conflict(
+            current_version(&pool, planting_id).await?,
+            format!("crop_planting with id {} not found", planting_id),
+        ));
+    }
+
+    tracing::info!("crop_planting {planting_id} deleted by {}", claims.sub);
+
+    Ok(())
+}
+
+async fn current_version(pool: &PgPool, planting_id: i32) -> Result<Option<i32>, SorjordetError> {
+    let version = query_scalar!(
+        "SELECT version FROM crop_planting WHERE id = $1",
+        planting_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(version)
+}
+
+pub fn crop_planting_router() -> Router<PgPool> {
+    Router::new()
+        .route("/warnings", get(get_rotation_warnings))
+        .route("/field/{field_id}/rotation", get(get_field_rotation))
+        .route(
+            "/{planting_id}",
+            axum::routing::patch(patch_planting).delete(delete_planting),
+        )
+        .route("/", get(get_plantings).post(post_planting))
+}
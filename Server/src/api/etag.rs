@@ -0,0 +1,58 @@
+use axum::{
+    extract::FromRequestParts,
+    http::{
+        HeaderName,
+        header::{ETAG, IF_MATCH},
+        request::Parts,
+    },
+};
+
+use crate::errors::SorjordetError;
+
+/// The row version a client expects to be modifying, taken from the `If-Match` header.
+/// `None` when the header is missing or `*`, in which case any version is accepted.
+pub struct IfMatch(pub Option<i32>);
+
+impl<S> FromRequestParts<S> for IfMatch
+where
+    S: Send + Sync,
+{
+    type Rejection = SorjordetError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Some(header) = parts.headers.get(IF_MATCH) else {
+            return Ok(IfMatch(None));
+        };
+
+        let value = header.to_str().unwrap_or_default().trim();
+        if value == "*" {
+            return Ok(IfMatch(None));
+        }
+
+        // Only strong ETags issued by `etag` can match, anything else fails the precondition.
+        value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .and_then(|v| v.parse::<i32>().ok())
+            .map(|version| IfMatch(Some(version)))
+            .ok_or_else(|| {
+                SorjordetError::PreconditionFailed(format!("If-Match {value} does not match"))
+            })
+    }
+}
+
+/// `ETag` response header for a row version.
+pub fn etag(version: i32) -> [(HeaderName, String); 1] {
+    [(ETAG, format!("\"{version}\""))]
+}
+
+/// Explains why a conditional update or delete touched no rows,
+/// given the current version of the row if it still exists.
+pub fn not_found_or_conflict(current_version: Option<i32>, not_found: String) -> SorjordetError {
+    match current_version {
+        Some(version) => SorjordetError::PreconditionFailed(format!(
+            "Changed by someone else, current version is {version}"
+        )),
+        None => SorjordetError::NotFound(not_found),
+    }
+}
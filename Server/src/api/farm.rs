@@ -2,30 +2,167 @@ use axum::{
     self, Json, Router,
     extract::{self, State},
     response::IntoResponse,
-    routing::get,
+    routing::{get, patch},
 };
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
-use sqlx::{PgPool, query_as, query_scalar};
+use sqlx::{PgConnection, PgPool, query, query_as, query_scalar};
 use ts_rs::TS;
 
 use crate::auth::Claims;
 use crate::errors::SorjordetError;
 
+use super::patch::Patch;
+use super::period::SeasonStart;
+use super::validation::{Validate, ValidatedJson, validate_name};
+
+/// What happens when a field is drawn overlapping other fields of the farm.
+#[derive(Serialize, Deserialize, TS, sqlx::Type, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+#[ts(export)]
+pub enum OverlapPolicy {
+    Reject,
+    /// The field is saved, overlaps are listed by `/farm_fields/overlaps`.
+    #[default]
+    Warn,
+}
+
 #[derive(Serialize, Deserialize, FromRow, TS)]
 #[ts(export)]
 pub struct Farm {
     pub id: i32,
     pub name: String,
     pub farm_coordinates: String,
+    /// `MM-DD` first day of the farm's farming season.
+    pub season_start: String,
+    pub field_overlap_policy: OverlapPolicy,
+    pub created_at: DateTime<Utc>,
+    pub created_by: Option<String>,
+    pub updated_at: DateTime<Utc>,
+    pub updated_by: Option<String>,
+}
+
+#[derive(Deserialize, TS)]
+#[ts(export)]
+pub struct CreateFarm {
+    pub name: String,
+    pub farm_coordinates: String,
+    /// `MM-DD`, defaults to January 1st.
+    #[ts(as = "Option<String>", optional)]
+    pub season_start: Option<SeasonStart>,
+    #[serde(default)]
+    #[ts(optional)]
+    pub field_overlap_policy: Option<OverlapPolicy>,
+}
+
+impl Validate for CreateFarm {
+    fn validate(&self) -> Result<(), SorjordetError> {
+        validate_name("name", &self.name, 256)?;
+        validate_name("farm_coordinates", &self.farm_coordinates, usize::MAX)
+    }
+}
+
+/// JSON merge patch for a farm, only the given members are changed.
+#[derive(Deserialize, TS)]
+#[ts(export)]
+pub struct UpdateFarm {
+    #[serde(default)]
+    #[ts(as = "Option<String>", optional)]
+    pub name: Patch<String>,
+    #[serde(default)]
+    #[ts(as = "Option<String>", optional)]
+    pub farm_coordinates: Patch<String>,
+    #[serde(default)]
+    #[ts(as = "Option<String>", optional)]
+    pub season_start: Patch<SeasonStart>,
+    #[serde(default)]
+    #[ts(as = "Option<OverlapPolicy>", optional)]
+    pub field_overlap_policy: Patch<OverlapPolicy>,
+}
+
+impl Validate for UpdateFarm {
+    fn validate(&self) -> Result<(), SorjordetError> {
+        self.name.reject_null("name")?;
+        self.farm_coordinates.reject_null("farm_coordinates")?;
+        self.season_start.reject_null("season_start")?;
+        self.field_overlap_policy
+            .reject_null("field_overlap_policy")?;
+        if let Some(name) = self.name.value() {
+            validate_name("name", name, 256)?;
+        }
+        if let Some(coordinates) = self.farm_coordinates.value() {
+            validate_name("farm_coordinates", coordinates, usize::MAX)?;
+        }
+        Ok(())
+    }
+}
+
+/// The season start configured for a farm.
+pub async fn farm_season_start(pool: &PgPool, farm_id: i32) -> Result<SeasonStart, SorjordetError> {
+    let result = query!(
+        "SELECT season_start_month, season_start_day FROM farm WHERE id = $1",
+        farm_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    let Some(farm) = result else {
+        return Err(SorjordetError::NotFound(format!(
+            "farm with id {} not found",
+            farm_id
+        )));
+    };
+
+    SeasonStart::new(farm.season_start_month as u32, farm.season_start_day as u32).ok_or_else(
+        || SorjordetError::InternalError(format!("farm {farm_id} has an invalid season start")),
+    )
+}
+
+/// How overlapping fields are handled on a farm.
+pub async fn farm_overlap_policy(
+    conn: &mut PgConnection,
+    farm_id: i32,
+) -> Result<OverlapPolicy, SorjordetError> {
+    // held until the transaction ends, so boundary changes on the farm are checked one at a time
+    let policy = query_scalar!(
+        r#"SELECT field_overlap_policy as "policy: OverlapPolicy"
+                FROM farm
+                WHERE id = $1
+                FOR NO KEY UPDATE
+            "#,
+        farm_id
+    )
+    .fetch_optional(conn)
+    .await?;
+
+    policy.ok_or_else(|| SorjordetError::NotFound(format!("farm with id {} not found", farm_id)))
+}
+
+/// Season start given in a request, falling back to the farm's season and then to calendar years.
+pub async fn resolve_season_start(
+    pool: &PgPool,
+    season_start: Option<SeasonStart>,
+    farm_id: Option<i32>,
+) -> Result<SeasonStart, SorjordetError> {
+    match (season_start, farm_id) {
+        (Some(season_start), _) => Ok(season_start),
+        (None, Some(farm_id)) => farm_season_start(pool, farm_id).await,
+        (None, None) => Ok(SeasonStart::default()),
+    }
 }
 
 async fn get_farms(State(pool): State<PgPool>) -> Result<impl IntoResponse, SorjordetError> {
     let result: Vec<Farm> = query_as!(
         Farm,
-        "SELECT id, name, farm_coordinates
+        r#"SELECT id, name, farm_coordinates,
+                    LPAD(season_start_month::text, 2, '0') || '-' || LPAD(season_start_day::text, 2, '0')
+                        as "season_start!",
+                    field_overlap_policy as "field_overlap_policy: OverlapPolicy",
+                    created_at, created_by, updated_at, updated_by
                 FROM farm
-            "
+            "#
     )
     .fetch_all(&pool)
     .await?;
@@ -36,15 +173,22 @@ async fn get_farms(State(pool): State<PgPool>) -> Result<impl IntoResponse, Sorj
 async fn post_farm(
     claims: Claims,
     State(pool): State<PgPool>,
-    extract::Json(payload): extract::Json<Farm>,
+    ValidatedJson(payload): ValidatedJson<CreateFarm>,
 ) -> Result<impl IntoResponse, SorjordetError> {
+    let season_start = payload.season_start.unwrap_or_default();
     let result = query_scalar!(
-        "INSERT INTO farm (name, farm_coordinates)
-                VALUES ($1,$2)
+        "INSERT INTO farm
+                    (name, farm_coordinates, season_start_month, season_start_day,
+                    field_overlap_policy, created_by, updated_by)
+                VALUES ($1, $2, $3, $4, $6, $5, $5)
                 RETURNING id
             ",
         &payload.name,
-        &payload.farm_coordinates
+        &payload.farm_coordinates,
+        season_start.month() as i32,
+        season_start.day() as i32,
+        &claims.sub,
+        payload.field_overlap_policy.unwrap_or_default() as OverlapPolicy
     )
     .fetch_one(&pool)
     .await?;
@@ -54,6 +198,49 @@ async fn post_farm(
     Ok(Json(result))
 }
 
+async fn patch_farm(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    extract::Path(farm_id): extract::Path<i32>,
+    ValidatedJson(payload): ValidatedJson<UpdateFarm>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    let season_start = payload.season_start.value();
+    let result = query!(
+        "UPDATE farm
+                SET name = COALESCE($1, name),
+                    farm_coordinates = COALESCE($2, farm_coordinates),
+                    season_start_month = COALESCE($3, season_start_month),
+                    season_start_day = COALESCE($4, season_start_day),
+                    field_overlap_policy = COALESCE($7, field_overlap_policy),
+                    updated_at = NOW(),
+                    updated_by = $5
+                WHERE id = $6
+            ",
+        payload.name.value(),
+        payload.farm_coordinates.value(),
+        season_start.map(|x| x.month() as i32),
+        season_start.map(|x| x.day() as i32),
+        &claims.sub,
+        farm_id,
+        payload.field_overlap_policy.value().copied() as Option<OverlapPolicy>
+    )
+    .execute(&pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(SorjordetError::NotFound(format!(
+            "farm with id {} not found",
+            farm_id
+        )));
+    }
+
+    tracing::info!("farm {farm_id} updated by {}", claims.sub);
+
+    Ok(())
+}
+
 pub fn farm_router() -> Router<PgPool> {
-    Router::new().route("/", get(get_farms).post(post_farm))
+    Router::new()
+        .route("/{farm_id}", patch(patch_farm))
+        .route("/", get(get_farms).post(post_farm))
 }
@@ -2,26 +2,111 @@ use axum::{
     self, Json, Router,
     extract::{self, State},
     response::IntoResponse,
-    routing::{delete, get},
+    routing::{delete, get, post},
 };
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::{FromRow, query};
-use sqlx::{PgPool, query_as, query_scalar};
+use sqlx::{PgConnection, PgExecutor, PgPool, query_as, query_scalar};
 use ts_rs::TS;
 
 use crate::auth::Claims;
 use crate::errors::SorjordetError;
 
+use super::etag::{IfMatch, etag, not_found_or_conflict};
+use super::farm::{OverlapPolicy, farm_overlap_policy};
+use super::patch::Patch;
+use super::validation::{
+    Validate, ValidatedJson, validate_geojson, validate_geojson_line, validate_name,
+};
+
 #[derive(Serialize, Deserialize, FromRow, TS)]
 #[ts(export)]
 pub struct FarmField {
     pub id: i32,
+    pub name: String,
+    /// Boundary as a GeoJSON Feature in EPSG:3857, empty if the field has none.
+    pub map_polygon_string: String,
+    /// Area of the boundary in square metres.
+    pub area_m2: Option<f64>,
+    pub farm_id: i32,
+    pub farm_field_group_id: Option<i32>,
+    pub version: i32,
+    /// Ids of the field's tags, see `/field_tags`.
+    pub tags: Vec<i32>,
+    /// When the field was replaced by a split or merge, see `/farm_fields/{id}/lineage`.
+    pub retired_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub created_by: Option<String>,
+    pub updated_at: DateTime<Utc>,
+    pub updated_by: Option<String>,
+}
+
+/// Queries `FarmField`s, the select list and `FROM farm_field` followed by the
+/// given clauses. Expands to a single literal so sqlx still checks the query.
+macro_rules! query_farm_fields {
+    ($clauses:tt $(, $args:expr)* $(,)?) => {
+        query_as!(
+            FarmField,
+            r#"SELECT id, name, COALESCE(field_boundary_geojson(boundary), '') as "map_polygon_string!",
+                    ST_Area(boundary::geography) as area_m2,
+                    farm_field_group_id, farm_id, version,
+                    ARRAY(
+                        SELECT tag_id FROM farm_field_tag WHERE field_id = farm_field.id ORDER BY tag_id
+                    ) as "tags!",
+                    retired_at, created_at, created_by, updated_at, updated_by
+                FROM farm_field
+            "# + $clauses
+            $(, $args)*
+        )
+    };
+}
+
+#[derive(Deserialize, TS)]
+#[ts(export)]
+pub struct CreateFarmField {
     pub name: String,
     pub map_polygon_string: String,
     pub farm_id: i32,
     pub farm_field_group_id: Option<i32>,
 }
 
+impl Validate for CreateFarmField {
+    fn validate(&self) -> Result<(), SorjordetError> {
+        validate_name("name", &self.name, 256)?;
+        validate_geojson("map_polygon_string", &self.map_polygon_string)
+    }
+}
+
+/// JSON merge patch for a field, only the given members are changed.
+#[derive(Deserialize, TS)]
+#[ts(export)]
+pub struct UpdateFarmField {
+    #[serde(default)]
+    #[ts(as = "Option<String>", optional)]
+    pub name: Patch<String>,
+    #[serde(default)]
+    #[ts(as = "Option<String>", optional)]
+    pub map_polygon_string: Patch<String>,
+    #[serde(default)]
+    #[ts(as = "Option<i32>", optional = nullable)]
+    pub farm_field_group_id: Patch<i32>,
+}
+
+impl Validate for UpdateFarmField {
+    fn validate(&self) -> Result<(), SorjordetError> {
+        self.name.reject_null("name")?;
+        self.map_polygon_string.reject_null("map_polygon_string")?;
+        if let Some(name) = self.name.value() {
+            validate_name("name", name, 256)?;
+        }
+        if let Some(polygon) = self.map_polygon_string.value() {
+            validate_geojson("map_polygon_string", polygon)?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Serialize, Deserialize, FromRow, TS)]
 #[ts(export)]
 pub struct FarmFieldMeta {
@@ -30,15 +115,359 @@ pub struct FarmFieldMeta {
     pub farm_id: i32,
 }
 
+/// Replaces a field by new fields, cut either along `cut_line` or drawn as `polygons`.
+/// The field is retired and keeps its events and harvests.
+#[derive(Deserialize, TS)]
+#[ts(export)]
+pub struct SplitFarmField {
+    /// Names of the new fields, in the order of `polygons`,
+    /// or from west to east for the parts cut by `cut_line`.
+    pub names: Vec<String>,
+    /// GeoJSON LineString in EPSG:3857 across the field's boundary.
+    #[ts(optional)]
+    pub cut_line: Option<String>,
+    /// GeoJSON boundaries of the new fields.
+    #[ts(optional)]
+    pub polygons: Option<Vec<String>>,
+}
+
+impl Validate for SplitFarmField {
+    fn validate(&self) -> Result<(), SorjordetError> {
+        if self.names.len() < 2 {
+            return Err(SorjordetError::InvalidInput(
+                "a field must be split into at least two fields".to_string(),
+            ));
+        }
+        for (i, name) in self.names.iter().enumerate() {
+            validate_name("names", name, 256)?;
+            if self.names[..i].iter().any(|x| x.trim() == name.trim()) {
+                return Err(SorjordetError::InvalidInput(format!(
+                    "names contains {} more than once",
+                    name.trim()
+                )));
+            }
+        }
+        match (&self.cut_line, &self.polygons) {
+            (Some(line), None) => validate_geojson_line("cut_line", line),
+            (None, Some(polygons)) if polygons.len() == self.names.len() => polygons
+                .iter()
+                .try_for_each(|x| validate_geojson("polygons", x)),
+            (None, Some(_)) => Err(SorjordetError::InvalidInput(
+                "polygons and names must have the same length".to_string(),
+            )),
+            _ => Err(SorjordetError::InvalidInput(
+                "either cut_line or polygons must be given".to_string(),
+            )),
+        }
+    }
+}
+
+/// Replaces fields of a farm by one field covering all of them.
+/// The merged fields are retired and keep their events and harvests.
+#[derive(Deserialize, TS)]
+#[ts(export)]
+pub struct MergeFarmFields {
+    pub field_ids: Vec<i32>,
+    pub name: String,
+    pub farm_field_group_id: Option<i32>,
+}
+
+impl Validate for MergeFarmFields {
+    fn validate(&self) -> Result<(), SorjordetError> {
+        validate_name("name", &self.name, 256)?;
+        let mut ids = self.field_ids.clone();
+        ids.sort_unstable();
+        ids.dedup();
+        if ids.len() < 2 || ids.len() != self.field_ids.len() {
+            return Err(SorjordetError::InvalidInput(
+                "field_ids must be at least two different fields".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, TS, sqlx::Type, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+#[ts(export)]
+pub enum LineageOperation {
+    Split,
+    Merge,
+}
+
+/// A field another field was split or merged from, or into.
+#[derive(Serialize, TS)]
+#[ts(export)]
+pub struct FieldLineageLink {
+    pub field_id: i32,
+    pub field_name: String,
+    pub operation: LineageOperation,
+    pub retired_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub created_by: Option<String>,
+}
+
+/// Direct predecessors and successors of a field.
+#[derive(Serialize, TS)]
+#[ts(export)]
+pub struct FarmFieldLineage {
+    pub field_id: i32,
+    /// Fields this field was split or merged from.
+    pub predecessors: Vec<FieldLineageLink>,
+    /// Fields this field was split or merged into.
+    pub successors: Vec<FieldLineageLink>,
+}
+
+/// A boundary a field has had, kept when the field is redrawn.
+#[derive(Serialize, FromRow, TS)]
+#[ts(export)]
+pub struct FieldBoundaryVersion {
+    pub id: i32,
+    pub field_id: i32,
+    /// Boundary as a GeoJSON Feature in EPSG:3857.
+    pub map_polygon_string: String,
+    pub area_m2: f64,
+    pub valid_from: DateTime<Utc>,
+    /// `None` for the current boundary.
+    pub valid_to: Option<DateTime<Utc>>,
+    /// Version of the field the boundary was drawn in.
+    pub field_version: i32,
+    pub created_at: DateTime<Utc>,
+    pub created_by: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct FieldListParams {
+    /// Include fields retired by a split or merge, which are left out by default.
+    #[serde(default)]
+    include_retired: bool,
+    /// Only fields with this tag.
+    tag_id: Option<i32>,
+}
+
+#[derive(Deserialize)]
+struct ExtentParams {
+    farm_id: Option<i32>,
+}
+
+/// A position in WGS84 degrees.
+#[derive(Deserialize)]
+struct PointParams {
+    lon: f64,
+    lat: f64,
+    farm_id: Option<i32>,
+}
+
+/// A viewport in WGS84 degrees.
+#[derive(Deserialize)]
+struct BboxParams {
+    min_lon: f64,
+    min_lat: f64,
+    max_lon: f64,
+    max_lat: f64,
+    farm_id: Option<i32>,
+}
+
+fn validate_lon_lat(lon: f64, lat: f64) -> Result<(), SorjordetError> {
+    if !(-180.0..=180.0).contains(&lon) || !(-90.0..=90.0).contains(&lat) {
+        return Err(SorjordetError::InvalidInput(format!(
+            "({lon}, {lat}) is not a WGS84 longitude and latitude"
+        )));
+    }
+    Ok(())
+}
+
+/// Bounding box of field boundaries in WGS84 degrees.
+#[derive(Serialize, TS)]
+#[ts(export)]
+pub struct FieldExtent {
+    pub min_lon: f64,
+    pub min_lat: f64,
+    pub max_lon: f64,
+    pub max_lat: f64,
+}
+
+/// Intersections smaller than this are digitising noise along shared borders.
+const MIN_OVERLAP_M2: f64 = 1.0;
+
+#[derive(Deserialize)]
+struct OverlapParams {
+    farm_id: Option<i32>,
+}
+
+/// A proposed boundary to check against the other fields of a farm.
+#[derive(Deserialize, TS)]
+#[ts(export)]
+pub struct CheckFieldOverlap {
+    pub farm_id: i32,
+    pub map_polygon_string: String,
+    /// Field being redrawn, left out of the comparison.
+    pub field_id: Option<i32>,
+}
+
+impl Validate for CheckFieldOverlap {
+    fn validate(&self) -> Result<(), SorjordetError> {
+        validate_geojson("map_polygon_string", &self.map_polygon_string)
+    }
+}
+
+/// An existing field overlapped by a boundary.
+#[derive(Serialize, TS)]
+#[ts(export)]
+pub struct FieldOverlap {
+    pub field_id: i32,
+    pub field_name: String,
+    /// Area of the intersection in square metres.
+    pub overlap_m2: f64,
+}
+
+/// A created or updated field, with the fields its boundary overlaps when the
+/// farm only warns about overlaps.
+#[derive(Serialize, TS)]
+#[ts(export)]
+pub struct SavedFarmField {
+    pub id: i32,
+    pub version: i32,
+    pub overlaps: Vec<FieldOverlap>,
+}
+
+/// Two fields of a farm whose boundaries overlap.
+#[derive(Serialize, TS)]
+#[ts(export)]
+pub struct FieldOverlapPair {
+    pub farm_id: i32,
+    pub field_id: i32,
+    pub field_name: String,
+    pub other_field_id: i32,
+    pub other_field_name: String,
+    pub overlap_m2: f64,
+}
+
+/// Maps errors saving a field. GeoJSON without any polygon area ends up as an empty
+/// boundary, which is rejected, and active fields must have unique names.
+pub fn field_save_error(err: sqlx::Error) -> SorjordetError {
+    match &err {
+        sqlx::Error::Database(db) if db.is_check_violation() => SorjordetError::InvalidInput(
+            "map_polygon_string must contain a polygon with an area".to_string(),
+        ),
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            SorjordetError::Conflict("a field with this name already exists".to_string())
+        }
+        _ => err.into(),
+    }
+}
+
+/// Those of `names` already used by active fields.
+pub async fn taken_names(
+    executor: impl PgExecutor<'_>,
+    names: &[String],
+) -> Result<Vec<String>, SorjordetError> {
+    let taken = query_scalar!(
+        "SELECT name FROM farm_field WHERE retired_at IS NULL AND name = ANY($1) ORDER BY name",
+        names
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(taken)
+}
+
+/// Rejects new fields named like an active field, naming the field.
+pub async fn ensure_names_free(
+    executor: impl PgExecutor<'_>,
+    names: &[String],
+) -> Result<(), SorjordetError> {
+    match taken_names(executor, names).await?.first() {
+        Some(name) => Err(SorjordetError::Conflict(format!(
+            "a field named {name} already exists"
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Fields on `farm_id`, other than `field_id`, that `geojson` overlaps.
+pub async fn find_overlaps(
+    executor: impl PgExecutor<'_>,
+    farm_id: i32,
+    geojson: &str,
+    field_id: Option<i32>,
+) -> Result<Vec<FieldOverlap>, SorjordetError> {
+    let overlaps = query!(
+        r#"SELECT f.id, f.name,
+                    ST_Area(ST_Intersection(f.boundary, b.geom)::geography) as "overlap_m2!"
+                FROM farm_field f, (SELECT field_boundary_from_geojson($1) AS geom) b
+                WHERE f.farm_id = $2
+                    AND f.retired_at IS NULL
+                    AND ($3::int IS NULL OR f.id <> $3)
+                    AND ST_Intersects(f.boundary, b.geom)
+                ORDER BY f.name
+            "#,
+        geojson,
+        farm_id,
+        field_id
+    )
+    .fetch_all(executor)
+    .await
+    .map_err(field_save_error)?;
+
+    Ok(overlaps
+        .into_iter()
+        .filter(|x| x.overlap_m2 >= MIN_OVERLAP_M2)
+        .map(|x| FieldOverlap {
+            field_id: x.id,
+            field_name: x.name,
+            overlap_m2: x.overlap_m2,
+        })
+        .collect())
+}
+
+/// Applies the farm's overlap policy to a new boundary for a field, returning the
+/// overlapped fields if the policy allows them. Run it in the transaction saving the
+/// boundary, which it keeps other boundary changes on the farm out of.
+pub async fn check_overlaps(
+    conn: &mut PgConnection,
+    farm_id: i32,
+    geojson: &str,
+    field_id: Option<i32>,
+) -> Result<Vec<FieldOverlap>, SorjordetError> {
+    let policy = farm_overlap_policy(&mut *conn, farm_id).await?;
+    let overlaps = find_overlaps(&mut *conn, farm_id, geojson, field_id).await?;
+    if overlaps.is_empty() {
+        return Ok(overlaps);
+    }
+
+    let listed = overlaps
+        .iter()
+        .map(|x| format!("{} ({:.0} m²)", x.field_name, x.overlap_m2))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    match policy {
+        OverlapPolicy::Reject => Err(SorjordetError::Conflict(format!(
+            "boundary overlaps other fields: {listed}"
+        ))),
+        OverlapPolicy::Warn => {
+            tracing::warn!("boundary on farm {farm_id} overlaps other fields: {listed}");
+            Ok(overlaps)
+        }
+    }
+}
+
 async fn get_all_farm_fields(
     State(pool): State<PgPool>,
+    extract::Query(params): extract::Query<FieldListParams>,
 ) -> Result<impl IntoResponse, SorjordetError> {
-    let result: Vec<FarmField> = query_as!(
-        FarmField,
-        "SELECT id, name, map_polygon_string, farm_field_group_id, farm_id
-                FROM farm_field
+    let result: Vec<FarmField> = query_farm_fields!(
+        r#"
+            WHERE ($1 OR retired_at IS NULL)
+                AND ($2::int IS NULL OR EXISTS (
+                    SELECT 1 FROM farm_field_tag WHERE field_id = farm_field.id AND tag_id = $2
+                ))
             ORDER BY name
-        "
+        "#,
+        params.include_retired,
+        params.tag_id
     )
     .fetch_all(&pool)
     .await?;
@@ -51,7 +480,7 @@ async fn get_farm_fields_meta(
 ) -> Result<impl IntoResponse, SorjordetError> {
     let result = query_as!(
         FarmFieldMeta,
-        "SELECT id, name, farm_id FROM farm_field ORDER BY name"
+        "SELECT id, name, farm_id FROM farm_field WHERE retired_at IS NULL ORDER BY name"
     )
     .fetch_all(&pool)
     .await?;
@@ -59,33 +488,234 @@ async fn get_farm_fields_meta(
     Ok(Json(result))
 }
 
+/// Every pair of overlapping fields, each pair listed once.
+async fn get_farm_field_overlaps(
+    State(pool): State<PgPool>,
+    extract::Query(params): extract::Query<OverlapParams>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    let pairs = query!(
+        r#"SELECT a.farm_id, a.id, a.name, b.id as other_id, b.name as other_name,
+                    ST_Area(ST_Intersection(a.boundary, b.boundary)::geography) as "overlap_m2!"
+                FROM farm_field a
+                JOIN farm_field b ON b.farm_id = a.farm_id AND b.id > a.id
+                WHERE ($1::int IS NULL OR a.farm_id = $1)
+                    AND a.retired_at IS NULL
+                    AND b.retired_at IS NULL
+                    AND ST_Intersects(a.boundary, b.boundary)
+                ORDER BY a.farm_id, a.name, b.name
+            "#,
+        params.farm_id
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    let result: Vec<FieldOverlapPair> = pairs
+        .into_iter()
+        .filter(|x| x.overlap_m2 >= MIN_OVERLAP_M2)
+        .map(|x| FieldOverlapPair {
+            farm_id: x.farm_id,
+            field_id: x.id,
+            field_name: x.name,
+            other_field_id: x.other_id,
+            other_field_name: x.other_name,
+            overlap_m2: x.overlap_m2,
+        })
+        .collect();
+
+    Ok(Json(result))
+}
+
+/// Fields a proposed boundary would overlap, regardless of the farm's policy.
+async fn post_farm_field_overlaps(
+    State(pool): State<PgPool>,
+    ValidatedJson(payload): ValidatedJson<CheckFieldOverlap>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    let result = find_overlaps(
+        &pool,
+        payload.farm_id,
+        &payload.map_polygon_string,
+        payload.field_id,
+    )
+    .await?;
+
+    Ok(Json(result))
+}
+
+/// Fields whose boundary contains the position, normally at most one.
+async fn get_farm_fields_at(
+    State(pool): State<PgPool>,
+    extract::Query(params): extract::Query<PointParams>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    validate_lon_lat(params.lon, params.lat)?;
+
+    let result: Vec<FarmField> = query_farm_fields!(
+        r#"
+            WHERE ST_Intersects(boundary, ST_SetSRID(ST_Point($1, $2), 4326))
+                AND retired_at IS NULL
+                AND ($3::int IS NULL OR farm_id = $3)
+            ORDER BY name
+        "#,
+        params.lon,
+        params.lat,
+        params.farm_id
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(result))
+}
+
+/// Fields whose boundary intersects the bounding box.
+async fn get_farm_fields_in_bbox(
+    State(pool): State<PgPool>,
+    extract::Query(params): extract::Query<BboxParams>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    validate_lon_lat(params.min_lon, params.min_lat)?;
+    validate_lon_lat(params.max_lon, params.max_lat)?;
+    if params.min_lon > params.max_lon || params.min_lat > params.max_lat {
+        return Err(SorjordetError::InvalidInput(
+            "bounding box minimum must not be greater than its maximum".to_string(),
+        ));
+    }
+
+    let result: Vec<FarmField> = query_farm_fields!(
+        r#"
+            WHERE ST_Intersects(boundary, ST_MakeEnvelope($1, $2, $3, $4, 4326))
+                AND retired_at IS NULL
+                AND ($5::int IS NULL OR farm_id = $5)
+            ORDER BY name
+        "#,
+        params.min_lon,
+        params.min_lat,
+        params.max_lon,
+        params.max_lat,
+        params.farm_id
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(result))
+}
+
+/// Bounding box of all field boundaries, `None` when no field has one.
+async fn get_farm_fields_extent(
+    State(pool): State<PgPool>,
+    extract::Query(params): extract::Query<ExtentParams>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    let extent = query!(
+        "SELECT MIN(ST_XMin(boundary)) as min_lon, MIN(ST_YMin(boundary)) as min_lat,
+                    MAX(ST_XMax(boundary)) as max_lon, MAX(ST_YMax(boundary)) as max_lat
+                FROM farm_field
+                WHERE ($1::int IS NULL OR farm_id = $1)
+                    AND retired_at IS NULL
+            ",
+        params.farm_id
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    let result = match (
+        extent.min_lon,
+        extent.min_lat,
+        extent.max_lon,
+        extent.max_lat,
+    ) {
+        (Some(min_lon), Some(min_lat), Some(max_lon), Some(max_lat)) => Some(FieldExtent {
+            min_lon,
+            min_lat,
+            max_lon,
+            max_lat,
+        }),
+        _ => None,
+    };
+
+    Ok(Json(result))
+}
+
 async fn get_farm_field_by_id(
     extract::Path(field_id): extract::Path<i32>,
     State(pool): State<PgPool>,
 ) -> Result<impl IntoResponse, SorjordetError> {
-    let result: FarmField = query_as!(
-        FarmField,
-        "SELECT id, name, map_polygon_string, farm_field_group_id, farm_id
-                FROM farm_field WHERE id = $1
-            ",
+    let result: Option<FarmField> = query_farm_fields!("WHERE id = $1", field_id)
+        .fetch_optional(&pool)
+        .await?;
+
+    let Some(field) = result else {
+        return Err(SorjordetError::NotFound(format!(
+            "field with id {} not found",
+            field_id
+        )));
+    };
+
+    Ok((etag(field.version), Json(field)))
+}
+
+/// Boundaries of a field, newest first.
+async fn get_farm_field_boundaries(
+    extract::Path(field_id): extract::Path<i32>,
+    State(pool): State<PgPool>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    if current_version(&pool, field_id).await?.is_none() {
+        return Err(SorjordetError::NotFound(format!(
+            "field with id {} not found",
+            field_id
+        )));
+    }
+
+    let result: Vec<FieldBoundaryVersion> = query_as!(
+        FieldBoundaryVersion,
+        r#"SELECT id, field_id, field_boundary_geojson(boundary) as "map_polygon_string!",
+                    ST_Area(boundary::geography) as "area_m2!",
+                    valid_from, valid_to, field_version, created_at, created_by
+                FROM farm_field_boundary
+                WHERE field_id = $1
+                ORDER BY valid_from DESC, id DESC
+            "#,
         field_id
     )
-    .fetch_one(&pool)
+    .fetch_all(&pool)
     .await?;
 
     Ok(Json(result))
 }
 
+async fn get_farm_field_boundary(
+    extract::Path((field_id, boundary_id)): extract::Path<(i32, i32)>,
+    State(pool): State<PgPool>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    let result: Option<FieldBoundaryVersion> = query_as!(
+        FieldBoundaryVersion,
+        r#"SELECT id, field_id, field_boundary_geojson(boundary) as "map_polygon_string!",
+                    ST_Area(boundary::geography) as "area_m2!",
+                    valid_from, valid_to, field_version, created_at, created_by
+                FROM farm_field_boundary
+                WHERE field_id = $1 AND id = $2
+            "#,
+        field_id,
+        boundary_id
+    )
+    .fetch_optional(&pool)
+    .await?;
+
+    let Some(boundary) = result else {
+        return Err(SorjordetError::NotFound(format!(
+            "boundary with id {} not found for field {}",
+            boundary_id, field_id
+        )));
+    };
+
+    Ok(Json(boundary))
+}
+
 async fn get_farm_field_by_group_id(
     extract::Path(group_id): extract::Path<i32>,
     State(pool): State<PgPool>,
 ) -> Result<impl IntoResponse, SorjordetError> {
-    let result: Vec<FarmField> = query_as!(
-        FarmField,
-        "SELECT id, name, map_polygon_string, farm_field_group_id, farm_id
-                        FROM farm_field
-                        WHERE farm_field_group_id = $1
-                    ",
+    let result: Vec<FarmField> = query_farm_fields!(
+        r#"
+            WHERE farm_field_group_id = $1
+                AND retired_at IS NULL
+        "#,
         group_id
     )
     .fetch_all(&pool)
@@ -97,77 +727,504 @@ async fn get_farm_field_by_group_id(
 async fn post_farm_field(
     claims: Claims,
     State(pool): State<PgPool>,
-    extract::Json(payload): extract::Json<FarmField>,
+    ValidatedJson(payload): ValidatedJson<CreateFarmField>,
 ) -> Result<impl IntoResponse, SorjordetError> {
-    let result = query_scalar!(
-        "INSERT INTO farm_field (name, farm_id, farm_field_group_id, map_polygon_string)
-                VALUES ($1,$2,$3,$4)
-                RETURNING id
+    let mut tx = pool.begin().await?;
+
+    let overlaps =
+        check_overlaps(&mut tx, payload.farm_id, &payload.map_polygon_string, None).await?;
+
+    let result = query!(
+        "INSERT INTO farm_field
+                    (name, farm_id, farm_field_group_id, boundary, created_by, updated_by)
+                VALUES ($1, $2, $3, field_boundary_from_geojson($4), $5, $5)
+                RETURNING id, version
             ",
         &payload.name,
         &payload.farm_id,
         payload.farm_field_group_id,
-        &payload.map_polygon_string
+        &payload.map_polygon_string,
+        &claims.sub
     )
-    .fetch_one(&pool)
-    .await?;
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(field_save_error)?;
+
+    tx.commit().await?;
 
     tracing::info!("new field inserted by {}", claims.sub);
 
-    Ok(Json(result))
+    Ok(Json(SavedFarmField {
+        id: result.id,
+        version: result.version,
+        overlaps,
+    }))
 }
 
 async fn patch_farm_field(
     claims: Claims,
     State(pool): State<PgPool>,
     extract::Path(field_id): extract::Path<i32>,
-    extract::Json(payload): extract::Json<FarmField>,
+    IfMatch(expected_version): IfMatch,
+    ValidatedJson(payload): ValidatedJson<UpdateFarmField>,
 ) -> Result<impl IntoResponse, SorjordetError> {
-    if payload.name.is_empty() {
-        return Err(SorjordetError::InvalidInput(
-            "name must not be empty".to_string(),
-        ));
+    let mut tx = pool.begin().await?;
+
+    let mut overlaps = Vec::new();
+    if let Some(polygon) = payload.map_polygon_string.value() {
+        let farm_id = query_scalar!("SELECT farm_id FROM farm_field WHERE id = $1", field_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| {
+                SorjordetError::NotFound(format!("field with id {} not found", field_id))
+            })?;
+        overlaps = check_overlaps(&mut tx, farm_id, polygon, Some(field_id)).await?;
     }
-    let result = query!(
+
+    let result = query_scalar!(
         "UPDATE farm_field
-                SET name = $1, farm_field_group_id = $2, map_polygon_string = $3
-                WHERE id = $4
+                SET name = COALESCE($1, name),
+                    farm_field_group_id = CASE WHEN $2 THEN $3 ELSE farm_field_group_id END,
+                    boundary = COALESCE(field_boundary_from_geojson($4), boundary),
+                    version = version + 1,
+                    updated_at = NOW(),
+                    updated_by = $7
+                WHERE id = $5 AND ($6::int IS NULL OR version = $6)
+                RETURNING version
             ",
-        &payload.name,
-        payload.farm_field_group_id,
-        &payload.map_polygon_string,
-        &field_id
+        payload.name.value(),
+        payload.farm_field_group_id.is_set(),
+        payload.farm_field_group_id.value(),
+        payload.map_polygon_string.value(),
+        &field_id,
+        expected_version,
+        &claims.sub
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(field_save_error)?;
+
+    let Some(version) = result else {
+        tracing::info!("field {} not updated", field_id);
+        return Err(not_found_or_conflict(
+            current_version(&pool, field_id).await?,
+            format!("field with id {} not found", field_id),
+        ));
+    };
+
+    tx.commit().await?;
+
+    tracing::info!("field {} updated by {}", field_id, claims.sub);
+
+    Ok((
+        etag(version),
+        Json(SavedFarmField {
+            id: field_id,
+            version,
+            overlaps,
+        }),
+    ))
+}
+
+async fn delete_farm_field(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    extract::Path(field_id): extract::Path<i32>,
+    IfMatch(expected_version): IfMatch,
+) -> Result<impl IntoResponse, SorjordetError> {
+    let result = query!(
+        "DELETE FROM farm_field WHERE id = $1 AND ($2::int IS NULL OR version = $2)",
+        field_id,
+        expected_version
     )
     .execute(&pool)
     .await?;
 
     if result.rows_affected() == 0 {
-        tracing::info!("field {} not found", field_id);
+        return Err(not_found_or_conflict(
+            current_version(&pool, field_id).await?,
+            format!("field with id {} not found", field_id),
+        ));
+    }
+
+    tracing::info!("field {} deleted by {}", field_id, claims.sub);
+
+    Ok(())
+}
+
+/// New events and harvests can only be logged on fields that are not retired.
+pub async fn ensure_active_field(pool: &PgPool, field_id: i32) -> Result<(), SorjordetError> {
+    let retired = query_scalar!(
+        "SELECT retired_at IS NOT NULL as \"retired!\" FROM farm_field WHERE id = $1",
+        field_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    match retired {
+        None => Err(SorjordetError::InvalidInput(format!(
+            "field {field_id} not found"
+        ))),
+        Some(true) => Err(SorjordetError::InvalidInput(format!(
+            "field {field_id} is retired"
+        ))),
+        Some(false) => Ok(()),
+    }
+}
+
+async fn split_farm_field(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    extract::Path(field_id): extract::Path<i32>,
+    IfMatch(expected_version): IfMatch,
+    ValidatedJson(payload): ValidatedJson<SplitFarmField>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    let names: Vec<String> = payload.names.iter().map(|x| x.trim().to_string()).collect();
+    let mut tx = pool.begin().await?;
+
+    let Some(field) = query!(
+        "SELECT farm_id, version, retired_at FROM farm_field WHERE id = $1 FOR UPDATE",
+        field_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    else {
         return Err(SorjordetError::NotFound(format!(
             "field with id {} not found",
             field_id
         )));
+    };
+    if expected_version.is_some_and(|x| x != field.version) {
+        return Err(not_found_or_conflict(
+            Some(field.version),
+            format!("field with id {} not found", field_id),
+        ));
+    }
+    if field.retired_at.is_some() {
+        return Err(SorjordetError::InvalidInput(format!(
+            "field {field_id} is retired"
+        )));
     }
 
-    tracing::info!("field {} updated by {}", field_id, claims.sub);
+    // retired first, so a part can keep the field's name
+    retire_fields(&mut tx, &[field_id], &claims.sub).await?;
+    ensure_names_free(&mut *tx, &names).await?;
 
-    Ok(())
+    let new_ids = match (&payload.cut_line, &payload.polygons) {
+        (Some(line), _) => {
+            let parts = query_scalar!(
+                r#"SELECT COUNT(*) as "count!"
+                        FROM farm_field f, field_boundary_split(f.boundary, $2) s
+                        WHERE f.id = $1
+                    "#,
+                field_id,
+                line
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+            if parts != names.len() as i64 {
+                return Err(SorjordetError::InvalidInput(format!(
+                    "cut_line splits the field into {parts} parts, but {} names were given",
+                    names.len()
+                )));
+            }
+
+            query_scalar!(
+                "INSERT INTO farm_field
+                            (name, farm_id, farm_field_group_id, boundary, created_by, updated_by)
+                        SELECT n.name, f.farm_id, f.farm_field_group_id, s.geom, $3, $3
+                        FROM farm_field f
+                            CROSS JOIN field_boundary_split(f.boundary, $2) s
+                            JOIN UNNEST($4::text[]) WITH ORDINALITY AS n(name, part)
+                                ON n.part = s.part
+                        WHERE f.id = $1
+                        ORDER BY s.part
+                        RETURNING id
+                    ",
+                field_id,
+                line,
+                &claims.sub,
+                &names
+            )
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(field_save_error)?
+        }
+        (None, Some(polygons)) => {
+            for polygon in polygons {
+                check_overlaps(&mut tx, field.farm_id, polygon, Some(field_id)).await?;
+            }
+
+            let mut ids = Vec::with_capacity(polygons.len());
+            for (name, polygon) in names.iter().zip(polygons) {
+                let id = query_scalar!(
+                    "INSERT INTO farm_field
+                                (name, farm_id, farm_field_group_id, boundary, created_by, updated_by)
+                            SELECT $1, farm_id, farm_field_group_id, field_boundary_from_geojson($2), $3, $3
+                            FROM farm_field WHERE id = $4
+                            RETURNING id
+                        ",
+                    name,
+                    polygon,
+                    &claims.sub,
+                    field_id
+                )
+                .fetch_one(&mut *tx)
+                .await
+                .map_err(field_save_error)?;
+                ids.push(id);
+            }
+
+            // drawn parts must divide the field, whatever the farm's overlap policy
+            let overlapping = query!(
+                r#"SELECT a.name, b.name as other_name,
+                            ST_Area(ST_Intersection(a.boundary, b.boundary)::geography) as "overlap_m2!"
+                        FROM farm_field a
+                            JOIN farm_field b ON a.id < b.id
+                        WHERE a.id = ANY($1) AND b.id = ANY($1)
+                            AND ST_Intersects(a.boundary, b.boundary)
+                    "#,
+                &ids
+            )
+            .fetch_all(&mut *tx)
+            .await?;
+            if let Some(x) = overlapping.iter().find(|x| x.overlap_m2 >= MIN_OVERLAP_M2) {
+                return Err(SorjordetError::InvalidInput(format!(
+                    "polygons {} and {} overlap by {:.0} m²",
+                    x.name, x.other_name, x.overlap_m2
+                )));
+            }
+            ids
+        }
+        (None, None) => unreachable!("checked by SplitFarmField::validate"),
+    };
+
+    query!(
+        "INSERT INTO farm_field_lineage (parent_field_id, child_field_id, operation, created_by)
+                SELECT $1, UNNEST($2::int[]), $3, $4
+            ",
+        field_id,
+        &new_ids,
+        LineageOperation::Split as LineageOperation,
+        &claims.sub
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    copy_tags_to_successors(&mut tx, &[field_id]).await?;
+
+    tx.commit().await?;
+
+    tracing::info!(
+        "field {field_id} split into {:?} by {}",
+        new_ids,
+        claims.sub
+    );
+
+    Ok(Json(new_ids))
 }
 
-async fn delete_farm_field(
+async fn merge_farm_fields(
     claims: Claims,
     State(pool): State<PgPool>,
-    extract::Path(field_id): extract::Path<i32>,
+    ValidatedJson(payload): ValidatedJson<MergeFarmFields>,
 ) -> Result<impl IntoResponse, SorjordetError> {
-    query!("DELETE FROM farm_field WHERE id = $1", field_id)
-        .execute(&pool)
+    let mut tx = pool.begin().await?;
+
+    let fields = query!(
+        "SELECT id, farm_id, retired_at
+                FROM farm_field
+                WHERE id = ANY($1)
+                ORDER BY id
+                FOR UPDATE
+            ",
+        &payload.field_ids
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    if fields.len() != payload.field_ids.len() {
+        return Err(SorjordetError::NotFound(
+            "not all fields to merge were found".to_string(),
+        ));
+    }
+    if let Some(retired) = fields.iter().find(|x| x.retired_at.is_some()) {
+        return Err(SorjordetError::InvalidInput(format!(
+            "field {} is retired",
+            retired.id
+        )));
+    }
+    let farm_id = fields[0].farm_id;
+    if fields.iter().any(|x| x.farm_id != farm_id) {
+        return Err(SorjordetError::InvalidInput(
+            "fields on different farms can not be merged".to_string(),
+        ));
+    }
+    if let Some(group_id) = payload.farm_field_group_id {
+        let group_farm_id = query_scalar!(
+            "SELECT farm_id FROM farm_field_group WHERE id = $1",
+            group_id
+        )
+        .fetch_optional(&mut *tx)
         .await?;
+        if group_farm_id != Some(farm_id) {
+            return Err(SorjordetError::InvalidInput(format!(
+                "farm_field_group {group_id} not found on the farm of the fields"
+            )));
+        }
+    }
+
+    // retired first, so the merged field can keep the name of one of them
+    retire_fields(&mut tx, &payload.field_ids, &claims.sub).await?;
+    ensure_names_free(&mut *tx, &[payload.name.trim().to_string()]).await?;
+
+    let merged_id = query_scalar!(
+        "INSERT INTO farm_field
+                    (name, farm_id, farm_field_group_id, boundary, created_by, updated_by)
+                SELECT $2, $3, $4,
+                    ST_Multi(ST_CollectionExtract(ST_MakeValid(ST_Union(boundary)), 3)), $5, $5
+                FROM farm_field
+                WHERE id = ANY($1)
+                RETURNING id
+            ",
+        &payload.field_ids,
+        payload.name.trim(),
+        farm_id,
+        payload.farm_field_group_id,
+        &claims.sub
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(field_save_error)?;
+
+    query!(
+        "INSERT INTO farm_field_lineage (parent_field_id, child_field_id, operation, created_by)
+                SELECT UNNEST($1::int[]), $2, $3, $4
+            ",
+        &payload.field_ids,
+        merged_id,
+        LineageOperation::Merge as LineageOperation,
+        &claims.sub
+    )
+    .execute(&mut *tx)
+    .await?;
 
-    tracing::info!("field {} inserted  by {}", field_id, claims.sub);
+    copy_tags_to_successors(&mut tx, &payload.field_ids).await?;
+
+    tx.commit().await?;
+
+    tracing::info!(
+        "fields {:?} merged into {merged_id} by {}",
+        payload.field_ids,
+        claims.sub
+    );
+
+    Ok(Json(merged_id))
+}
+
+/// Retires fields replaced by a split or merge. Retired fields free their names.
+async fn retire_fields(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    field_ids: &[i32],
+    user: &str,
+) -> Result<(), SorjordetError> {
+    query!(
+        "UPDATE farm_field
+                SET retired_at = NOW(),
+                    retired_by = $2,
+                    version = version + 1,
+                    updated_at = NOW(),
+                    updated_by = $2
+                WHERE id = ANY($1)
+            ",
+        field_ids,
+        user
+    )
+    .execute(&mut **tx)
+    .await?;
 
     Ok(())
 }
 
+/// Tags the successors of retired fields, recorded in the lineage, with their tags.
+async fn copy_tags_to_successors(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    field_ids: &[i32],
+) -> Result<(), SorjordetError> {
+    query!(
+        "INSERT INTO farm_field_tag (field_id, tag_id)
+                SELECT l.child_field_id, ft.tag_id
+                FROM farm_field_lineage l
+                    JOIN farm_field_tag ft ON ft.field_id = l.parent_field_id
+                WHERE l.parent_field_id = ANY($1)
+                ON CONFLICT DO NOTHING
+            ",
+        field_ids
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+async fn get_farm_field_lineage(
+    extract::Path(field_id): extract::Path<i32>,
+    State(pool): State<PgPool>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    if current_version(&pool, field_id).await?.is_none() {
+        return Err(SorjordetError::NotFound(format!(
+            "field with id {} not found",
+            field_id
+        )));
+    }
+
+    let links = query!(
+        r#"SELECT l.parent_field_id = $1 as "is_successor!",
+                    f.id, f.name, l.operation as "operation: LineageOperation",
+                    f.retired_at, l.created_at, l.created_by
+                FROM farm_field_lineage l
+                    JOIN farm_field f ON f.id = CASE WHEN l.parent_field_id = $1
+                        THEN l.child_field_id ELSE l.parent_field_id END
+                WHERE l.parent_field_id = $1 OR l.child_field_id = $1
+                ORDER BY f.name, f.id
+            "#,
+        field_id
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    let mut lineage = FarmFieldLineage {
+        field_id,
+        predecessors: Vec::new(),
+        successors: Vec::new(),
+    };
+    for x in links {
+        let link = FieldLineageLink {
+            field_id: x.id,
+            field_name: x.name,
+            operation: x.operation,
+            retired_at: x.retired_at,
+            created_at: x.created_at,
+            created_by: x.created_by,
+        };
+        if x.is_successor {
+            lineage.successors.push(link);
+        } else {
+            lineage.predecessors.push(link);
+        }
+    }
+
+    Ok(Json(lineage))
+}
+
+async fn current_version(pool: &PgPool, field_id: i32) -> Result<Option<i32>, SorjordetError> {
+    let version = query_scalar!("SELECT version FROM farm_field WHERE id = $1", field_id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(version)
+}
+
 pub fn farm_field_router() -> Router<PgPool> {
     Router::new()
         .route(
@@ -176,7 +1233,22 @@ pub fn farm_field_router() -> Router<PgPool> {
                 .get(get_farm_field_by_id)
                 .patch(patch_farm_field),
         )
+        .route("/{field_id}/boundaries", get(get_farm_field_boundaries))
+        .route("/{field_id}/split", post(split_farm_field))
+        .route("/{field_id}/lineage", get(get_farm_field_lineage))
+        .route("/merge", post(merge_farm_fields))
+        .route(
+            "/{field_id}/boundaries/{boundary_id}",
+            get(get_farm_field_boundary),
+        )
         .route("/group/{group_id}", get(get_farm_field_by_group_id))
         .route("/all", get(get_all_farm_fields))
+        .route("/extent", get(get_farm_fields_extent))
+        .route("/at", get(get_farm_fields_at))
+        .route("/bbox", get(get_farm_fields_in_bbox))
+        .route(
+            "/overlaps",
+            get(get_farm_field_overlaps).post(post_farm_field_overlaps),
+        )
         .route("/", get(get_farm_fields_meta).post(post_farm_field))
 }
@@ -4,8 +4,9 @@ use axum::{
     self, Json, Router,
     extract::{self, State},
     response::IntoResponse,
-    routing::get,
+    routing::{get, patch, put},
 };
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use sqlx::{PgPool, Row, query, query_scalar};
@@ -14,7 +15,10 @@ use ts_rs::TS;
 use crate::auth::Claims;
 use crate::errors::SorjordetError;
 
+use super::etag::{IfMatch, etag, not_found_or_conflict};
 use super::farm_field::FarmFieldMeta;
+use super::patch::Patch;
+use super::validation::{Validate, ValidatedJson, validate_color, validate_name};
 
 #[derive(Serialize, Deserialize, FromRow, TS)]
 #[ts(export)]
@@ -24,6 +28,65 @@ pub struct FarmFieldGroup {
     pub farm_id: i32,
     pub fields: Vec<i32>,
     pub draw_color: String,
+    pub version: i32,
+    pub created_at: DateTime<Utc>,
+    pub created_by: Option<String>,
+    pub updated_at: DateTime<Utc>,
+    pub updated_by: Option<String>,
+}
+
+#[derive(Deserialize, TS)]
+#[ts(export)]
+pub struct CreateFarmFieldGroup {
+    pub name: String,
+    pub farm_id: i32,
+    pub draw_color: String,
+}
+
+impl Validate for CreateFarmFieldGroup {
+    fn validate(&self) -> Result<(), SorjordetError> {
+        validate_name("name", &self.name, 256)?;
+        validate_color("draw_color", &self.draw_color, 64)
+    }
+}
+
+/// JSON merge patch for a group, only the given members are changed.
+#[derive(Deserialize, TS)]
+#[ts(export)]
+pub struct UpdateFarmFieldGroup {
+    #[serde(default)]
+    #[ts(as = "Option<String>", optional)]
+    pub name: Patch<String>,
+    #[serde(default)]
+    #[ts(as = "Option<String>", optional)]
+    pub draw_color: Patch<String>,
+}
+
+impl Validate for UpdateFarmFieldGroup {
+    fn validate(&self) -> Result<(), SorjordetError> {
+        self.name.reject_null("name")?;
+        self.draw_color.reject_null("draw_color")?;
+        if let Some(name) = self.name.value() {
+            validate_name("name", name, 256)?;
+        }
+        if let Some(color) = self.draw_color.value() {
+            validate_color("draw_color", color, 64)?;
+        }
+        Ok(())
+    }
+}
+
+/// The fields a group should have, fields not listed are taken out of the group.
+#[derive(Deserialize, TS)]
+#[ts(export)]
+pub struct SetFarmFieldGroupFields {
+    pub fields: Vec<i32>,
+}
+
+#[derive(Deserialize)]
+struct DeleteGroupParams {
+    /// Group the fields are moved to, they are left without a group when not given.
+    move_to: Option<i32>,
 }
 
 #[derive(Serialize, Deserialize, FromRow, TS)]
@@ -33,6 +96,7 @@ pub struct FarmFieldGroupMeta {
     pub farm_id: i32,
     pub name: String,
     pub draw_color: String,
+    pub version: i32,
     pub fields: Vec<FarmFieldMeta>,
 }
 
@@ -40,11 +104,13 @@ async fn get_farm_field_groups(
     State(pool): State<PgPool>,
 ) -> Result<impl IntoResponse, SorjordetError> {
     let groups = query!(
-        r#"SELECT g.id, g.name, g.farm_id, g.draw_color, 
+        r#"SELECT g.id, g.name, g.farm_id, g.draw_color, g.version,
+                    g.created_at, g.created_by, g.updated_at, g.updated_by,
                     ARRAY_AGG(f.id) 
                     filter (WHERE f.id IS NOT NULL) as fields
                 FROM farm_field_group AS g
-                    LEFT JOIN farm_field AS f ON g.id = f.farm_field_group_id 
+                    LEFT JOIN farm_field AS f ON g.id = f.farm_field_group_id
+                        AND f.retired_at IS NULL
                 GROUP BY g.id
                 ORDER BY g.name
                 "#
@@ -60,6 +126,11 @@ async fn get_farm_field_groups(
             draw_color: x.draw_color.to_string(),
             farm_id: x.farm_id,
             fields: x.fields.to_owned().unwrap_or_default(),
+            version: x.version,
+            created_at: x.created_at,
+            created_by: x.created_by.to_owned(),
+            updated_at: x.updated_at,
+            updated_by: x.updated_by.to_owned(),
         })
         .collect();
 
@@ -70,9 +141,9 @@ async fn get_farm_field_groups_meta(
     State(pool): State<PgPool>,
 ) -> Result<impl IntoResponse, SorjordetError> {
     let mut groups: Vec<FarmFieldGroupMeta> = query(
-        "SELECT fg.id as id, fg.name as name, fg.farm_id as farm_id, draw_color, f.id as field_id, f.name as field_name
+        "SELECT fg.id as id, fg.name as name, fg.farm_id as farm_id, draw_color, fg.version as version, f.id as field_id, f.name as field_name
                 FROM farm_field_group fg
-                LEFT JOIN farm_field f ON fg.id = f.farm_field_group_id
+                LEFT JOIN farm_field f ON fg.id = f.farm_field_group_id AND f.retired_at IS NULL
             ORDER BY fg.name, f.name
         ",
     )
@@ -86,6 +157,7 @@ async fn get_farm_field_groups_meta(
             let farm_id : i32 = x.get("farm_id");
             let name: String = x.get("name");
             let draw_color: String = x.get("draw_color");
+            let version: i32 = x.get("version");
             let field_id : Option<i32> = x.try_get("field_id").unwrap_or_default();
             let field_name: Option<String> = x.try_get("field_name").unwrap_or_default();
             let group = acc.entry(id).or_insert(FarmFieldGroupMeta {
@@ -93,6 +165,7 @@ async fn get_farm_field_groups_meta(
                 farm_id,
                 name: name.to_string(),
                 draw_color: draw_color.to_string(),
+                version,
                 fields: vec![],
             });
             if let Some(field_id) = field_id {
@@ -115,16 +188,17 @@ async fn get_farm_field_groups_meta(
 async fn post_farm_field_group(
     claims: Claims,
     State(pool): State<PgPool>,
-    extract::Json(payload): extract::Json<FarmFieldGroup>,
+    ValidatedJson(payload): ValidatedJson<CreateFarmFieldGroup>,
 ) -> Result<impl IntoResponse, SorjordetError> {
     let result = query_scalar!(
-        "INSERT INTO farm_field_group (name, farm_id, draw_color)
-                VALUES ($1,$2, $3)
+        "INSERT INTO farm_field_group (name, farm_id, draw_color, created_by, updated_by)
+                VALUES ($1,$2, $3, $4, $4)
                 RETURNING id
             ",
         &payload.name,
         &payload.farm_id,
-        &payload.draw_color
+        &payload.draw_color,
+        &claims.sub
     )
     .fetch_one(&pool)
     .await?;
@@ -137,46 +211,271 @@ async fn post_farm_field_group(
 async fn patch_farm_field_group(
     claims: Claims,
     State(pool): State<PgPool>,
-    extract::Json(payload): extract::Json<FarmFieldGroup>,
+    extract::Path(group_id): extract::Path<i32>,
+    IfMatch(expected_version): IfMatch,
+    ValidatedJson(payload): ValidatedJson<UpdateFarmFieldGroup>,
 ) -> Result<impl IntoResponse, SorjordetError> {
-    if payload.id <= 0 {
+    let result = query_scalar!(
+        r#"UPDATE farm_field_group
+            SET name = COALESCE($1, name),
+                draw_color = COALESCE($2, draw_color),
+                version = version + 1,
+                updated_at = NOW(),
+                updated_by = $5
+            WHERE id = $3 AND ($4::int IS NULL OR version = $4)
+            RETURNING version
+        "#,
+        payload.name.value(),
+        payload.draw_color.value(),
+        &group_id,
+        expected_version,
+        &claims.sub
+    )
+    .fetch_optional(&pool)
+    .await?;
+
+    let Some(version) = result else {
+        return Err(not_found_or_conflict(
+            current_version(&pool, group_id).await?,
+            format!("farm_field_group with id {} not found", group_id),
+        ));
+    };
+
+    tracing::info!("farm_field_group {} updated by {}", group_id, claims.sub);
+
+    Ok((etag(version), Json(group_id)))
+}
+
+/// Moves the listed fields into the group and takes the group's other fields out of it.
+async fn put_farm_field_group_fields(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    extract::Path(group_id): extract::Path<i32>,
+    IfMatch(expected_version): IfMatch,
+    Json(payload): Json<SetFarmFieldGroupFields>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    let mut fields = payload.fields;
+    fields.sort_unstable();
+    fields.dedup();
+
+    let mut tx = pool.begin().await?;
+
+    let Some(group) = query!(
+        "SELECT farm_id, version FROM farm_field_group WHERE id = $1 FOR UPDATE",
+        group_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    else {
         return Err(SorjordetError::NotFound(format!(
             "farm_field_group with id {} not found",
-            payload.id
+            group_id
         )));
+    };
+    if expected_version.is_some_and(|x| x != group.version) {
+        return Err(not_found_or_conflict(
+            Some(group.version),
+            format!("farm_field_group with id {} not found", group_id),
+        ));
     }
 
-    let result = query!(
-        r#"UPDATE farm_field_group
-            SET name = $1, draw_color = $2
-            WHERE id = $3
-        "#,
-        &payload.name,
-        &payload.draw_color,
-        &payload.id
+    let found = query!(
+        "SELECT id, farm_id, retired_at FROM farm_field WHERE id = ANY($1) FOR UPDATE",
+        &fields
     )
-    .execute(&pool)
+    .fetch_all(&mut *tx)
     .await?;
+    if let Some(missing) = fields.iter().find(|id| !found.iter().any(|x| x.id == **id)) {
+        return Err(SorjordetError::InvalidInput(format!(
+            "field {missing} not found"
+        )));
+    }
+    if let Some(field) = found.iter().find(|x| x.farm_id != group.farm_id) {
+        return Err(SorjordetError::InvalidInput(format!(
+            "field {} belongs to another farm than the group",
+            field.id
+        )));
+    }
+    if let Some(field) = found.iter().find(|x| x.retired_at.is_some()) {
+        return Err(SorjordetError::InvalidInput(format!(
+            "field {} is retired",
+            field.id
+        )));
+    }
+
+    // groups losing fields change too
+    query!(
+        "UPDATE farm_field_group
+                SET version = version + 1,
+                    updated_at = NOW(),
+                    updated_by = $3
+                WHERE id <> $1
+                    AND id IN (SELECT farm_field_group_id FROM farm_field WHERE id = ANY($2))
+            ",
+        group_id,
+        &fields,
+        &claims.sub
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    query!(
+        "UPDATE farm_field
+                SET farm_field_group_id = CASE WHEN id = ANY($2) THEN $1::int END,
+                    version = version + 1,
+                    updated_at = NOW(),
+                    updated_by = $3
+                WHERE (id = ANY($2) AND farm_field_group_id IS DISTINCT FROM $1)
+                    OR (farm_field_group_id = $1 AND NOT id = ANY($2) AND retired_at IS NULL)
+            ",
+        group_id,
+        &fields,
+        &claims.sub
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let version = query_scalar!(
+        "UPDATE farm_field_group
+                SET version = version + 1,
+                    updated_at = NOW(),
+                    updated_by = $2
+                WHERE id = $1
+                RETURNING version
+            ",
+        group_id,
+        &claims.sub
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    tracing::info!(
+        "fields of farm_field_group {} set by {}",
+        group_id,
+        claims.sub
+    );
+
+    Ok((etag(version), Json(fields)))
+}
+
+async fn delete_farm_field_group(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    extract::Path(group_id): extract::Path<i32>,
+    extract::Query(params): extract::Query<DeleteGroupParams>,
+    IfMatch(expected_version): IfMatch,
+) -> Result<impl IntoResponse, SorjordetError> {
+    if params.move_to == Some(group_id) {
+        return Err(SorjordetError::InvalidInput(
+            "fields can not be moved to the group being deleted".to_string(),
+        ));
+    }
 
-    if result.rows_affected() == 0 {
+    let mut tx = pool.begin().await?;
+
+    let Some(group) = query!(
+        "SELECT farm_id, version FROM farm_field_group WHERE id = $1 FOR UPDATE",
+        group_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    else {
         return Err(SorjordetError::NotFound(format!(
             "farm_field_group with id {} not found",
-            payload.id
+            group_id
         )));
+    };
+    if expected_version.is_some_and(|x| x != group.version) {
+        return Err(not_found_or_conflict(
+            Some(group.version),
+            format!("farm_field_group with id {} not found", group_id),
+        ));
+    }
+
+    if let Some(move_to) = params.move_to {
+        let target = query_scalar!(
+            "UPDATE farm_field_group
+                    SET version = version + 1,
+                        updated_at = NOW(),
+                        updated_by = $2
+                    WHERE id = $1
+                    RETURNING farm_id
+                ",
+            move_to,
+            &claims.sub
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        match target {
+            None => {
+                return Err(SorjordetError::InvalidInput(format!(
+                    "farm_field_group with id {move_to} not found"
+                )));
+            }
+            Some(farm_id) if farm_id != group.farm_id => {
+                return Err(SorjordetError::InvalidInput(
+                    "fields can only be moved to a group on the same farm".to_string(),
+                ));
+            }
+            Some(_) => {}
+        }
     }
 
-    tracing::info!("farm_field_group {} updated by {}", payload.id, claims.sub);
+    let moved = query!(
+        "UPDATE farm_field
+                SET farm_field_group_id = $2,
+                    version = version + 1,
+                    updated_at = NOW(),
+                    updated_by = $3
+                WHERE farm_field_group_id = $1
+            ",
+        group_id,
+        params.move_to,
+        &claims.sub
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    query!("DELETE FROM farm_field_group WHERE id = $1", group_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    tracing::info!(
+        "farm_field_group {} deleted by {}, {} fields {}",
+        group_id,
+        claims.sub,
+        moved.rows_affected(),
+        match params.move_to {
+            Some(move_to) => format!("moved to group {move_to}"),
+            None => "ungrouped".to_string(),
+        }
+    );
+
+    Ok(())
+}
+
+async fn current_version(pool: &PgPool, group_id: i32) -> Result<Option<i32>, SorjordetError> {
+    let version = query_scalar!(
+        "SELECT version FROM farm_field_group WHERE id = $1",
+        group_id
+    )
+    .fetch_optional(pool)
+    .await?;
 
-    Ok(Json(payload))
+    Ok(version)
 }
 
 pub fn farm_field_group_router() -> Router<PgPool> {
     Router::new()
         .route("/meta", get(get_farm_field_groups_meta))
+        .route("/{group_id}/fields", put(put_farm_field_group_fields))
         .route(
-            "/",
-            get(get_farm_field_groups)
-                .post(post_farm_field_group)
-                .patch(patch_farm_field_group),
+            "/{group_id}",
+            patch(patch_farm_field_group).delete(delete_farm_field_group),
         )
+        .route("/", get(get_farm_field_groups).post(post_farm_field_group))
 }
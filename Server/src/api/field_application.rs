@@ -0,0 +1,493 @@
+use axum::{
+    self, Json, Router,
+    extract::{self, State},
+    response::IntoResponse,
+    routing::{get, patch},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool, query, query_as, query_scalar};
+use ts_rs::TS;
+
+use crate::auth::Claims;
+use crate::errors::SorjordetError;
+
+use super::etag::{IfMatch, etag, not_found_or_conflict};
+use super::farm_field::ensure_active_field;
+use super::field_event_type::resolve_event_type;
+use super::patch::Patch;
+use super::period::SeasonStart;
+use super::validation::{
+    Validate, ValidatedJson, validate_length, validate_name, validate_non_negative,
+};
+
+#[derive(Serialize, Deserialize, TS, sqlx::Type, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+#[ts(export)]
+pub enum ApplicationKind {
+    Fertilizer,
+    Manure,
+    Pesticide,
+    Lime,
+    Other,
+}
+
+/// Unit the applied amount is given in.
+#[derive(Serialize, Deserialize, TS, sqlx::Type, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+#[ts(export)]
+pub enum ApplicationUnit {
+    Kg,
+    Tonne,
+    Litre,
+    CubicMetre,
+}
+
+/// A field event with a structured record of what was spread or sprayed.
+#[derive(Serialize, Deserialize, FromRow, TS)]
+#[ts(export)]
+pub struct FieldApplication {
+    /// Id of the field event the application belongs to.
+    pub id: i32,
+    pub time: DateTime<Utc>,
+    pub field_id: i32,
+    pub type_id: i32,
+    pub event_name: String,
+    pub description: Option<String>,
+    pub kind: ApplicationKind,
+    pub product: String,
+    pub amount: f64,
+    pub unit: ApplicationUnit,
+    /// Kilograms of nitrogen per unit of product, 0.22 for 22-3-10 spread in kg.
+    pub n_kg_per_unit: Option<f64>,
+    pub p_kg_per_unit: Option<f64>,
+    pub k_kg_per_unit: Option<f64>,
+    pub area_ha: Option<f64>,
+    pub operator: Option<String>,
+    pub weather: Option<String>,
+    /// Version of the field event, bumped by changes to the application as well.
+    pub version: i32,
+    pub created_at: DateTime<Utc>,
+    pub created_by: Option<String>,
+    pub updated_at: DateTime<Utc>,
+    pub updated_by: Option<String>,
+}
+
+/// Creates the field event and its application together.
+#[derive(Deserialize, TS)]
+#[ts(export)]
+pub struct CreateFieldApplication {
+    pub time: DateTime<Utc>,
+    pub field_id: i32,
+    #[serde(default)]
+    #[ts(optional)]
+    pub type_id: Option<i32>,
+    /// See `CreateFieldEvent::event_name`.
+    #[serde(default)]
+    #[ts(optional)]
+    pub event_name: Option<String>,
+    pub description: Option<String>,
+    pub kind: ApplicationKind,
+    pub product: String,
+    pub amount: f64,
+    pub unit: ApplicationUnit,
+    pub n_kg_per_unit: Option<f64>,
+    pub p_kg_per_unit: Option<f64>,
+    pub k_kg_per_unit: Option<f64>,
+    pub area_ha: Option<f64>,
+    pub operator: Option<String>,
+    pub weather: Option<String>,
+}
+
+impl Validate for CreateFieldApplication {
+    fn validate(&self) -> Result<(), SorjordetError> {
+        match (self.type_id, &self.event_name) {
+            (None, None) => {
+                return Err(SorjordetError::InvalidInput(
+                    "type_id or event_name is required".to_string(),
+                ));
+            }
+            (None, Some(event_name)) => validate_name("event_name", event_name, 128)?,
+            _ => {}
+        }
+        validate_length(
+            "description",
+            self.description.as_deref().unwrap_or_default(),
+            512,
+        )?;
+        validate_name("product", &self.product, 256)?;
+        validate_non_negative("amount", self.amount)?;
+        validate_contents(self.n_kg_per_unit, self.p_kg_per_unit, self.k_kg_per_unit)?;
+        validate_area(self.area_ha)?;
+        validate_length(
+            "operator",
+            self.operator.as_deref().unwrap_or_default(),
+            256,
+        )?;
+        validate_length("weather", self.weather.as_deref().unwrap_or_default(), 512)
+    }
+}
+
+/// JSON merge patch for the application, the event itself is changed through `/field_event`.
+#[derive(Deserialize, TS)]
+#[ts(export)]
+pub struct UpdateFieldApplication {
+    #[serde(default)]
+    #[ts(as = "Option<ApplicationKind>", optional)]
+    pub kind: Patch<ApplicationKind>,
+    #[serde(default)]
+    #[ts(as = "Option<String>", optional)]
+    pub product: Patch<String>,
+    #[serde(default)]
+    #[ts(as = "Option<f64>", optional)]
+    pub amount: Patch<f64>,
+    #[serde(default)]
+    #[ts(as = "Option<ApplicationUnit>", optional)]
+    pub unit: Patch<ApplicationUnit>,
+    #[serde(default)]
+    #[ts(as = "Option<f64>", optional = nullable)]
+    pub n_kg_per_unit: Patch<f64>,
+    #[serde(default)]
+    #[ts(as = "Option<f64>", optional = nullable)]
+    pub p_kg_per_unit: Patch<f64>,
+    #[serde(default)]
+    #[ts(as = "Option<f64>", optional = nullable)]
+    pub k_kg_per_unit: Patch<f64>,
+    #[serde(default)]
+    #[ts(as = "Option<f64>", optional = nullable)]
+    pub area_ha: Patch<f64>,
+    #[serde(default)]
+    #[ts(as = "Option<String>", optional = nullable)]
+    pub operator: Patch<String>,
+    #[serde(default)]
+    #[ts(as = "Option<String>", optional = nullable)]
+    pub weather: Patch<String>,
+}
+
+impl Validate for UpdateFieldApplication {
+    fn validate(&self) -> Result<(), SorjordetError> {
+        self.kind.reject_null("kind")?;
+        self.product.reject_null("product")?;
+        self.amount.reject_null("amount")?;
+        self.unit.reject_null("unit")?;
+        if let Some(product) = self.product.value() {
+            validate_name("product", product, 256)?;
+        }
+        if let Some(amount) = self.amount.value() {
+            validate_non_negative("amount", *amount)?;
+        }
+        validate_contents(
+            self.n_kg_per_unit.value().copied(),
+            self.p_kg_per_unit.value().copied(),
+            self.k_kg_per_unit.value().copied(),
+        )?;
+        validate_area(self.area_ha.value().copied())?;
+        if let Some(operator) = self.operator.value() {
+            validate_length("operator", operator, 256)?;
+        }
+        if let Some(weather) = self.weather.value() {
+            validate_length("weather", weather, 512)?;
+        }
+        Ok(())
+    }
+}
+
+fn validate_contents(n: Option<f64>, p: Option<f64>, k: Option<f64>) -> Result<(), SorjordetError> {
+    for (field, value) in [
+        ("n_kg_per_unit", n),
+        ("p_kg_per_unit", p),
+        ("k_kg_per_unit", k),
+    ] {
+        if let Some(value) = value {
+            validate_non_negative(field, value)?;
+        }
+    }
+    Ok(())
+}
+
+fn validate_area(area_ha: Option<f64>) -> Result<(), SorjordetError> {
+    if area_ha.is_some_and(|x| !x.is_finite() || x <= 0.0) {
+        return Err(SorjordetError::InvalidInput(
+            "area_ha must be greater than 0".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct FieldApplicationParams {
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    /// Farming season, using the season start of each field's farm.
+    season: Option<i32>,
+    field_id: Option<i32>,
+    farm_id: Option<i32>,
+}
+
+/// Nutrients applied to one field in one season. Applications without
+/// a given content don't add to that nutrient.
+#[derive(Serialize, TS)]
+#[ts(export)]
+pub struct FieldNutrientTotals {
+    field_id: i32,
+    field_name: String,
+    season: i32,
+    season_label: String,
+    applications: i64,
+    n_kg: f64,
+    p_kg: f64,
+    k_kg: f64,
+}
+
+async fn get_applications(
+    State(pool): State<PgPool>,
+    extract::Query(params): extract::Query<FieldApplicationParams>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    let result: Vec<FieldApplication> = query_as!(
+        FieldApplication,
+        r#"SELECT e.id, e.time, e.field_id, e.type_id, t.name as event_name, e.description,
+                    a.kind as "kind: ApplicationKind", a.product, a.amount,
+                    a.unit as "unit: ApplicationUnit",
+                    a.n_kg_per_unit, a.p_kg_per_unit, a.k_kg_per_unit,
+                    a.area_ha, a.operator, a.weather,
+                    e.version, e.created_at, e.created_by, e.updated_at, e.updated_by
+                FROM field_application AS a
+                    JOIN field_event AS e ON e.id = a.field_event_id
+                    JOIN field_event_type AS t ON t.id = e.type_id
+                    JOIN farm_field AS f ON f.id = e.field_id
+                    JOIN farm AS fa ON fa.id = f.farm_id
+                WHERE ($1::timestamptz IS NULL OR e.time >= $1)
+                    AND ($2::timestamptz IS NULL OR e.time < $2)
+                    AND ($3::int IS NULL OR e.field_id = $3)
+                    AND ($4::int IS NULL OR f.farm_id = $4)
+                    AND ($5::int IS NULL
+                        OR farm_season(e.time, fa.season_start_month, fa.season_start_day) = $5)
+                ORDER BY e.time DESC, e.id DESC
+            "#,
+        params.from,
+        params.to,
+        params.field_id,
+        params.farm_id,
+        params.season
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(result))
+}
+
+async fn get_nutrient_totals(
+    State(pool): State<PgPool>,
+    extract::Query(params): extract::Query<FieldApplicationParams>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    let rows = query!(
+        r#"SELECT f.id as field_id, f.name as field_name,
+                    farm_season(e.time, fa.season_start_month, fa.season_start_day) as "season!",
+                    fa.season_start_month, fa.season_start_day,
+                    COUNT(*) as "applications!",
+                    COALESCE(SUM(a.amount * a.n_kg_per_unit), 0) as "n_kg!",
+                    COALESCE(SUM(a.amount * a.p_kg_per_unit), 0) as "p_kg!",
+                    COALESCE(SUM(a.amount * a.k_kg_per_unit), 0) as "k_kg!"
+                FROM field_application AS a
+                    JOIN field_event AS e ON e.id = a.field_event_id
+                    JOIN farm_field AS f ON f.id = e.field_id
+                    JOIN farm AS fa ON fa.id = f.farm_id
+                WHERE ($1::timestamptz IS NULL OR e.time >= $1)
+                    AND ($2::timestamptz IS NULL OR e.time < $2)
+                    AND ($3::int IS NULL OR e.field_id = $3)
+                    AND ($4::int IS NULL OR f.farm_id = $4)
+                    AND ($5::int IS NULL
+                        OR farm_season(e.time, fa.season_start_month, fa.season_start_day) = $5)
+                GROUP BY f.id, fa.id, 3
+                ORDER BY f.name, f.id, 3 DESC
+            "#,
+        params.from,
+        params.to,
+        params.field_id,
+        params.farm_id,
+        params.season
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    let totals: Vec<FieldNutrientTotals> = rows
+        .into_iter()
+        .map(|x| {
+            let season_start =
+                SeasonStart::new(x.season_start_month as u32, x.season_start_day as u32)
+                    .unwrap_or_default();
+            FieldNutrientTotals {
+                field_id: x.field_id,
+                field_name: x.field_name,
+                season: x.season,
+                season_label: season_start.label(x.season),
+                applications: x.applications,
+                n_kg: x.n_kg,
+                p_kg: x.p_kg,
+                k_kg: x.k_kg,
+            }
+        })
+        .collect();
+
+    Ok(Json(totals))
+}
+
+async fn post_application(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    ValidatedJson(payload): ValidatedJson<CreateFieldApplication>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    ensure_active_field(&pool, payload.field_id).await?;
+
+    let mut tx = pool.begin().await?;
+
+    let type_id = resolve_event_type(
+        &mut tx,
+        payload.field_id,
+        payload.type_id,
+        payload.event_name.as_deref(),
+        &claims.sub,
+    )
+    .await?;
+
+    let event_id = query_scalar!(
+        "INSERT INTO field_event
+                    (time, field_id, type_id, description, created_by, updated_by)
+                VALUES ($1, $2, $3, $4, $5, $5)
+                RETURNING id
+            ",
+        &payload.time,
+        &payload.field_id,
+        type_id,
+        &payload.description.unwrap_or_default(),
+        &claims.sub
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    query!(
+        "INSERT INTO field_application
+                    (field_event_id, kind, product, amount, unit,
+                    n_kg_per_unit, p_kg_per_unit, k_kg_per_unit, area_ha, operator, weather)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            ",
+        event_id,
+        payload.kind as ApplicationKind,
+        payload.product.trim(),
+        payload.amount,
+        payload.unit as ApplicationUnit,
+        payload.n_kg_per_unit,
+        payload.p_kg_per_unit,
+        payload.k_kg_per_unit,
+        payload.area_ha,
+        payload.operator,
+        payload.weather
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    tracing::info!("new field_application inserted by {}", claims.sub);
+
+    Ok(Json(event_id))
+}
+
+async fn patch_application(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    extract::Path(event_id): extract::Path<i32>,
+    IfMatch(expected_version): IfMatch,
+    ValidatedJson(payload): ValidatedJson<UpdateFieldApplication>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    let mut tx = pool.begin().await?;
+
+    // the event's version covers its application too
+    let result = query_scalar!(
+        "UPDATE field_event
+                SET version = version + 1,
+                    updated_at = NOW(),
+                    updated_by = $3
+                WHERE id = $1 AND ($2::int IS NULL OR version = $2)
+                    AND EXISTS (SELECT 1 FROM field_application WHERE field_event_id = $1)
+                RETURNING version
+            ",
+        event_id,
+        expected_version,
+        &claims.sub
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some(version) = result else {
+        return Err(not_found_or_conflict(
+            current_version(&pool, event_id).await?,
+            format!("field_application with id {} not found", event_id),
+        ));
+    };
+
+    query!(
+        "UPDATE field_application
+                SET kind = COALESCE($2, kind),
+                    product = COALESCE($3, product),
+                    amount = COALESCE($4, amount),
+                    unit = COALESCE($5, unit),
+                    n_kg_per_unit = CASE WHEN $6 THEN $7 ELSE n_kg_per_unit END,
+                    p_kg_per_unit = CASE WHEN $8 THEN $9 ELSE p_kg_per_unit END,
+                    k_kg_per_unit = CASE WHEN $10 THEN $11 ELSE k_kg_per_unit END,
+                    area_ha = CASE WHEN $12 THEN $13 ELSE area_ha END,
+                    operator = CASE WHEN $14 THEN $15 ELSE operator END,
+                    weather = CASE WHEN $16 THEN $17 ELSE weather END
+                WHERE field_event_id = $1
+            ",
+        event_id,
+        payload.kind.value().copied() as Option<ApplicationKind>,
+        payload.product.value().map(|x| x.trim()),
+        payload.amount.value(),
+        payload.unit.value().copied() as Option<ApplicationUnit>,
+        payload.n_kg_per_unit.is_set(),
+        payload.n_kg_per_unit.value(),
+        payload.p_kg_per_unit.is_set(),
+        payload.p_kg_per_unit.value(),
+        payload.k_kg_per_unit.is_set(),
+        payload.k_kg_per_unit.value(),
+        payload.area_ha.is_set(),
+        payload.area_ha.value(),
+        payload.operator.is_set(),
+        payload.operator.value(),
+        payload.weather.is_set(),
+        payload.weather.value()
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    tracing::info!("field_application {event_id} updated by {}", claims.sub);
+
+    Ok(etag(version))
+}
+
+/// Version of the field event, if it has an application.
+async fn current_version(pool: &PgPool, event_id: i32) -> Result<Option<i32>, SorjordetError> {
+    let version = query_scalar!(
+        "SELECT e.version
+                FROM field_event AS e JOIN field_application AS a ON a.field_event_id = e.id
+                WHERE e.id = $1
+            ",
+        event_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(version)
+}
+
+pub fn field_application_router() -> Router<PgPool> {
+    Router::new()
+        .route("/nutrient_totals", get(get_nutrient_totals))
+        .route("/{event_id}", patch(patch_application))
+        .route("/", get(get_applications).post(post_application))
+}
@@ -12,27 +12,310 @@ use ts_rs::TS;
 use crate::auth::Claims;
 use crate::errors::SorjordetError;
 
+use super::etag::{IfMatch, etag, not_found_or_conflict};
+use super::farm_field::ensure_active_field;
+use super::field_event_type::resolve_event_type;
+use super::patch::Patch;
+use super::validation::{Validate, ValidatedJson, validate_length, validate_name};
+
 #[derive(Serialize, Deserialize, FromRow, TS)]
 #[ts(export)]
 pub struct FieldEvent {
     pub id: i32,
     pub time: DateTime<Utc>,
     pub field_id: i32,
+    pub type_id: i32,
+    /// Name of the event's type.
     pub event_name: String,
     pub description: Option<String>,
+    pub version: i32,
+    pub created_at: DateTime<Utc>,
+    pub created_by: Option<String>,
+    pub updated_at: DateTime<Utc>,
+    pub updated_by: Option<String>,
 }
 
-async fn get_all_events(State(pool): State<PgPool>) -> Result<impl IntoResponse, SorjordetError> {
-    let result: Vec<FieldEvent> = query_as!(
+#[derive(Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct CreateFieldEvent {
+    pub time: DateTime<Utc>,
+    pub field_id: i32,
+    #[serde(default)]
+    #[ts(optional)]
+    pub type_id: Option<i32>,
+    /// Name of the event type, matched ignoring case and created on the
+    /// field's farm if missing. Used when `type_id` is not given.
+    #[serde(default)]
+    #[ts(optional)]
+    pub event_name: Option<String>,
+    pub description: Option<String>,
+}
+
+impl Validate for CreateFieldEvent {
+    fn validate(&self) -> Result<(), SorjordetError> {
+        match (self.type_id, &self.event_name) {
+            (None, None) => {
+                return Err(SorjordetError::InvalidInput(
+                    "type_id or event_name is required".to_string(),
+                ));
+            }
+            (None, Some(event_name)) => validate_name("event_name", event_name, 128)?,
+            _ => {}
+        }
+        validate_length(
+            "description",
+            self.description.as_deref().unwrap_or_default(),
+            512,
+        )
+    }
+}
+
+/// JSON merge patch for a field event, only the given members are changed.
+#[derive(Deserialize, TS)]
+#[ts(export)]
+pub struct UpdateFieldEvent {
+    #[serde(default)]
+    #[ts(as = "Option<DateTime<Utc>>", optional)]
+    pub time: Patch<DateTime<Utc>>,
+    #[serde(default)]
+    #[ts(as = "Option<i32>", optional)]
+    pub field_id: Patch<i32>,
+    #[serde(default)]
+    #[ts(as = "Option<i32>", optional)]
+    pub type_id: Patch<i32>,
+    /// See `CreateFieldEvent::event_name`.
+    #[serde(default)]
+    #[ts(as = "Option<String>", optional)]
+    pub event_name: Patch<String>,
+    #[serde(default)]
+    #[ts(as = "Option<String>", optional = nullable)]
+    pub description: Patch<String>,
+}
+
+impl Validate for UpdateFieldEvent {
+    fn validate(&self) -> Result<(), SorjordetError> {
+        self.time.reject_null("time")?;
+        self.field_id.reject_null("field_id")?;
+        self.type_id.reject_null("type_id")?;
+        self.event_name.reject_null("event_name")?;
+        if let Some(event_name) = self.event_name.value() {
+            validate_name("event_name", event_name, 128)?;
+        }
+        if let Some(description) = self.description.value() {
+            validate_length("description", description, 512)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Serialize, TS)]
+#[ts(export)]
+struct FieldEventParams {
+    /// `next_cursor` from the previous page, omitted for the first page.
+    cursor: Option<String>,
+    limit: Option<i64>,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    /// Farming season, using the season start of each field's farm.
+    season: Option<i32>,
+    field_id: Option<i32>,
+    group_id: Option<i32>,
+    /// Only events on fields with this tag.
+    tag_id: Option<i32>,
+    farm_id: Option<i32>,
+    type_id: Option<i32>,
+    /// Case-insensitive substring match on the event type name.
+    search: Option<String>,
+}
+
+#[derive(Serialize, TS)]
+#[ts(export)]
+struct FieldEventPage {
+    events: Vec<FieldEvent>,
+    /// Cursor for the next page, `None` when this is the last page.
+    next_cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct FieldEventStatsParams {
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    /// Farming season, using the season start of each field's farm.
+    season: Option<i32>,
+    field_id: Option<i32>,
+    group_id: Option<i32>,
+    tag_id: Option<i32>,
+    farm_id: Option<i32>,
+}
+
+/// Number of events of one type, and of fields they were on.
+#[derive(Serialize, TS)]
+#[ts(export)]
+struct FieldEventTypeStats {
+    type_id: i32,
+    type_name: String,
+    event_count: i64,
+    field_count: i64,
+    first_time: DateTime<Utc>,
+    last_time: DateTime<Utc>,
+}
+
+const DEFAULT_PAGE_LIMIT: i64 = 50;
+const MAX_PAGE_LIMIT: i64 = 200;
+
+/// Position of the last event on a page, events are ordered by (time, id) descending.
+struct EventCursor {
+    time: DateTime<Utc>,
+    id: i32,
+}
+
+impl EventCursor {
+    fn encode(&self) -> String {
+        format!("{}_{}", self.time.timestamp_micros(), self.id)
+    }
+
+    fn decode(cursor: &str) -> Result<Self, SorjordetError> {
+        let invalid = || SorjordetError::InvalidInput(format!("invalid cursor {cursor}"));
+        let (micros, id) = cursor.split_once('_').ok_or_else(invalid)?;
+        let micros = micros.parse::<i64>().map_err(|_| invalid())?;
+        Ok(EventCursor {
+            time: DateTime::from_timestamp_micros(micros).ok_or_else(invalid)?,
+            id: id.parse::<i32>().map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// Escapes LIKE wildcards so user input is matched literally.
+fn like_pattern(search: &str) -> String {
+    let escaped = search
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{escaped}%")
+}
+
+async fn get_all_events(
+    State(pool): State<PgPool>,
+    extract::Query(params): extract::Query<FieldEventParams>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    let limit = params.limit.unwrap_or(DEFAULT_PAGE_LIMIT);
+    if !(1..=MAX_PAGE_LIMIT).contains(&limit) {
+        return Err(SorjordetError::InvalidInput(format!(
+            "limit must be between 1 and {MAX_PAGE_LIMIT}"
+        )));
+    }
+    let cursor = params
+        .cursor
+        .as_deref()
+        .map(EventCursor::decode)
+        .transpose()?;
+    let search = params
+        .search
+        .as_deref()
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+        .map(like_pattern);
+
+    // fetch one extra row to find out if there is a next page
+    let mut events: Vec<FieldEvent> = query_as!(
         FieldEvent,
-        "SELECT id, time, field_id, event_name, description
-                FROM field_event
-                ORDER BY time DESC"
+        "SELECT e.id, e.time, e.field_id, e.type_id, t.name as event_name, e.description,
+                    e.version, e.created_at, e.created_by, e.updated_at, e.updated_by
+                FROM field_event AS e
+                    JOIN field_event_type AS t ON t.id = e.type_id
+                    JOIN farm_field AS f ON f.id = e.field_id
+                    JOIN farm AS fa ON fa.id = f.farm_id
+                WHERE ($1::timestamptz IS NULL OR e.time >= $1)
+                    AND ($2::timestamptz IS NULL OR e.time < $2)
+                    AND ($3::int IS NULL OR e.field_id = $3)
+                    AND ($4::int IS NULL OR f.farm_field_group_id = $4)
+                    AND ($5::int IS NULL OR f.farm_id = $5)
+                    AND ($6::text IS NULL OR t.name ILIKE $6)
+                    AND ($7::timestamptz IS NULL OR (e.time, e.id) < ($7, $8))
+                    AND ($10::int IS NULL
+                        OR farm_season(e.time, fa.season_start_month, fa.season_start_day) = $10)
+                    AND ($11::int IS NULL OR e.type_id = $11)
+                    AND ($12::int IS NULL OR EXISTS (
+                        SELECT 1 FROM farm_field_tag ft WHERE ft.field_id = f.id AND ft.tag_id = $12
+                    ))
+                ORDER BY e.time DESC, e.id DESC
+                LIMIT $9
+            ",
+        params.from,
+        params.to,
+        params.field_id,
+        params.group_id,
+        params.farm_id,
+        search,
+        cursor.as_ref().map(|c| c.time),
+        cursor.as_ref().map(|c| c.id),
+        limit + 1,
+        params.season,
+        params.type_id,
+        params.tag_id
     )
     .fetch_all(&pool)
     .await?;
 
-    Ok(Json(result))
+    let next_cursor = if events.len() as i64 > limit {
+        events.truncate(limit as usize);
+        events.last().map(|e| {
+            EventCursor {
+                time: e.time,
+                id: e.id,
+            }
+            .encode()
+        })
+    } else {
+        None
+    };
+
+    Ok(Json(FieldEventPage {
+        events,
+        next_cursor,
+    }))
+}
+
+async fn get_event_statistics(
+    State(pool): State<PgPool>,
+    extract::Query(params): extract::Query<FieldEventStatsParams>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    let stats: Vec<FieldEventTypeStats> = query_as!(
+        FieldEventTypeStats,
+        r#"SELECT t.id as type_id, t.name as type_name,
+                    COUNT(*) as "event_count!",
+                    COUNT(DISTINCT e.field_id) as "field_count!",
+                    MIN(e.time) as "first_time!",
+                    MAX(e.time) as "last_time!"
+                FROM field_event AS e
+                    JOIN field_event_type AS t ON t.id = e.type_id
+                    JOIN farm_field AS f ON f.id = e.field_id
+                    JOIN farm AS fa ON fa.id = f.farm_id
+                WHERE ($1::timestamptz IS NULL OR e.time >= $1)
+                    AND ($2::timestamptz IS NULL OR e.time < $2)
+                    AND ($3::int IS NULL OR e.field_id = $3)
+                    AND ($4::int IS NULL OR f.farm_field_group_id = $4)
+                    AND ($5::int IS NULL OR f.farm_id = $5)
+                    AND ($6::int IS NULL
+                        OR farm_season(e.time, fa.season_start_month, fa.season_start_day) = $6)
+                    AND ($7::int IS NULL OR EXISTS (
+                        SELECT 1 FROM farm_field_tag ft WHERE ft.field_id = f.id AND ft.tag_id = $7
+                    ))
+                GROUP BY t.id
+                ORDER BY t.name
+            "#,
+        params.from,
+        params.to,
+        params.field_id,
+        params.group_id,
+        params.farm_id,
+        params.season,
+        params.tag_id
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(stats))
 }
 
 async fn get_events(
@@ -41,10 +324,11 @@ async fn get_events(
 ) -> Result<impl IntoResponse, SorjordetError> {
     let result: Vec<FieldEvent> = query_as!(
         FieldEvent,
-        "SELECT id, time, field_id, event_name, description 
-                FROM field_event
-                WHERE field_id = $1
-                ORDER BY time DESC
+        "SELECT e.id, e.time, e.field_id, e.type_id, t.name as event_name, e.description,
+                    e.version, e.created_at, e.created_by, e.updated_at, e.updated_by
+                FROM field_event AS e JOIN field_event_type AS t ON t.id = e.type_id
+                WHERE e.field_id = $1
+                ORDER BY e.time DESC
             ",
         field_id
     )
@@ -57,21 +341,38 @@ async fn get_events(
 async fn post_event(
     claims: Claims,
     State(pool): State<PgPool>,
-    extract::Json(payload): extract::Json<FieldEvent>,
+    ValidatedJson(payload): ValidatedJson<CreateFieldEvent>,
 ) -> Result<impl IntoResponse, SorjordetError> {
+    ensure_active_field(&pool, payload.field_id).await?;
+
+    let mut tx = pool.begin().await?;
+
+    let type_id = resolve_event_type(
+        &mut tx,
+        payload.field_id,
+        payload.type_id,
+        payload.event_name.as_deref(),
+        &claims.sub,
+    )
+    .await?;
+
     let result = query_scalar!(
-        "INSERT INTO field_event (time, field_id, event_name, description)
-                VALUES ($1, $2, $3, $4)
+        "INSERT INTO field_event
+                    (time, field_id, type_id, description, created_by, updated_by)
+                VALUES ($1, $2, $3, $4, $5, $5)
                 RETURNING id
             ",
         &payload.time,
         &payload.field_id,
-        &payload.event_name,
-        &payload.description.unwrap_or_default()
+        type_id,
+        &payload.description.unwrap_or_default(),
+        &claims.sub
     )
-    .fetch_one(&pool)
+    .fetch_one(&mut *tx)
     .await?;
 
+    tx.commit().await?;
+
     tracing::info!("new field_event inserted by {}", claims.sub);
 
     Ok(Json(result))
@@ -81,50 +382,108 @@ async fn patch_event(
     claims: Claims,
     State(pool): State<PgPool>,
     extract::Path(event_id): extract::Path<i32>,
-    extract::Json(payload): extract::Json<FieldEvent>,
+    IfMatch(expected_version): IfMatch,
+    ValidatedJson(payload): ValidatedJson<UpdateFieldEvent>,
 ) -> Result<impl IntoResponse, SorjordetError> {
-    let result = query!(
+    if let Some(field_id) = payload.field_id.value() {
+        ensure_active_field(&pool, *field_id).await?;
+    }
+
+    let mut tx = pool.begin().await?;
+
+    // moving the event or changing its type needs a type on the field's farm
+    let type_id =
+        if payload.field_id.is_set() || payload.type_id.is_set() || payload.event_name.is_set() {
+            let current = query!(
+                "SELECT field_id, type_id FROM field_event WHERE id = $1",
+                event_id
+            )
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| {
+                SorjordetError::NotFound(format!("field_event with id {} not found", event_id))
+            })?;
+            let type_id = match (payload.type_id.value(), payload.event_name.value()) {
+                (None, Some(_)) => None,
+                (type_id, _) => Some(type_id.copied().unwrap_or(current.type_id)),
+            };
+            Some(
+                resolve_event_type(
+                    &mut tx,
+                    payload
+                        .field_id
+                        .value()
+                        .copied()
+                        .unwrap_or(current.field_id),
+                    type_id,
+                    payload.event_name.value().map(String::as_str),
+                    &claims.sub,
+                )
+                .await?,
+            )
+        } else {
+            None
+        };
+
+    let result = query_scalar!(
         "UPDATE field_event
-                SET time = $1, field_id = $2, event_name = $3, description = $4
-                WHERE id = $5
+                SET time = COALESCE($1, time),
+                    field_id = COALESCE($2, field_id),
+                    type_id = COALESCE($3, type_id),
+                    description = CASE WHEN $4 THEN $5 ELSE description END,
+                    version = version + 1,
+                    updated_at = NOW(),
+                    updated_by = $8
+                WHERE id = $6 AND ($7::int IS NULL OR version = $7)
+                RETURNING version
             ",
-        &payload.time,
-        &payload.field_id,
-        &payload.event_name,
-        &payload.description.unwrap_or_default(),
-        &event_id
+        payload.time.value(),
+        payload.field_id.value(),
+        type_id,
+        payload.description.is_set(),
+        payload.description.value(),
+        &event_id,
+        expected_version,
+        &claims.sub
     )
-    .execute(&pool)
+    .fetch_optional(&mut *tx)
     .await?;
 
-    if result.rows_affected() == 0 {
-        tracing::info!("field_event {} not found", event_id);
-        return Err(SorjordetError::NotFound(format!(
-            "field_event with id {} not found",
-            event_id
-        )));
-    }
+    let Some(version) = result else {
+        tracing::info!("field_event {} not updated", event_id);
+        return Err(not_found_or_conflict(
+            current_version(&pool, event_id).await?,
+            format!("field_event with id {} not found", event_id),
+        ));
+    };
+
+    tx.commit().await?;
 
     tracing::info!("field_event {event_id} updated by {}", claims.sub);
 
-    Ok(())
+    Ok(etag(version))
 }
 
 async fn delete_event(
     claims: Claims,
     State(pool): State<PgPool>,
     extract::Path(event_id): extract::Path<i32>,
+    IfMatch(expected_version): IfMatch,
 ) -> Result<impl IntoResponse, SorjordetError> {
-    let result = query!("DELETE FROM field_event WHERE id = $1", event_id)
-        .execute(&pool)
-        .await?;
+    let result = query!(
+        "DELETE FROM field_event WHERE id = $1 AND ($2::int IS NULL OR version = $2)",
+        event_id,
+        expected_version
+    )
+    .execute(&pool)
+    .await?;
 
     if result.rows_affected() == 0 {
-        tracing::info!("field_event {} not found", event_id);
-        return Err(SorjordetError::NotFound(format!(
-            "field_event with id {} not found",
-            event_id
-        )));
+        tracing::info!("field_event {} not deleted", event_id);
+        return Err(not_found_or_conflict(
+            current_version(&pool, event_id).await?,
+            format!("field_event with id {} not found", event_id),
+        ));
     }
 
     tracing::info!("field_event {event_id} deleted by {}", claims.sub);
@@ -132,9 +491,18 @@ async fn delete_event(
     Ok(())
 }
 
+async fn current_version(pool: &PgPool, event_id: i32) -> Result<Option<i32>, SorjordetError> {
+    let version = query_scalar!("SELECT version FROM field_event WHERE id = $1", event_id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(version)
+}
+
 pub fn field_event_router() -> Router<PgPool> {
     Router::new()
         .route("/", get(get_all_events).post(post_event))
+        .route("/statistics", get(get_event_statistics))
         .route("/field/{field_id}", get(get_events))
         .route(
             "/{event_id}",
@@ -0,0 +1,335 @@
+use axum::{
+    self, Json, Router,
+    extract::{self, State},
+    response::IntoResponse,
+    routing::{get, patch, post},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgConnection, PgPool, query, query_as, query_scalar};
+use ts_rs::TS;
+
+use crate::auth::Claims;
+use crate::errors::SorjordetError;
+
+use super::catalogue::{
+    check_merge, insert_error, name_conflict, validate_type_name, validate_type_patch,
+};
+use super::patch::Patch;
+use super::validation::{Validate, ValidatedJson};
+
+#[derive(Serialize, Deserialize, FromRow, TS)]
+#[ts(export)]
+pub struct FieldEventType {
+    pub id: i32,
+    pub name: String,
+    pub farm_id: i32,
+    /// Archived types are kept for existing events but can't be used for new ones.
+    pub archived: bool,
+    pub created_at: DateTime<Utc>,
+    pub created_by: Option<String>,
+    pub updated_at: DateTime<Utc>,
+    pub updated_by: Option<String>,
+}
+
+#[derive(Deserialize, TS)]
+#[ts(export)]
+pub struct CreateFieldEventType {
+    pub name: String,
+    pub farm_id: i32,
+}
+
+impl Validate for CreateFieldEventType {
+    fn validate(&self) -> Result<(), SorjordetError> {
+        validate_type_name(&self.name)
+    }
+}
+
+/// JSON merge patch for a field event type, only the given members are changed.
+#[derive(Deserialize, TS)]
+#[ts(export)]
+pub struct UpdateFieldEventType {
+    #[serde(default)]
+    #[ts(as = "Option<String>", optional)]
+    pub name: Patch<String>,
+    #[serde(default)]
+    #[ts(as = "Option<bool>", optional)]
+    pub archived: Patch<bool>,
+}
+
+impl Validate for UpdateFieldEventType {
+    fn validate(&self) -> Result<(), SorjordetError> {
+        validate_type_patch(&self.name, &self.archived)
+    }
+}
+
+/// Reassigns every event of this type to `into_type_id` and removes this type.
+#[derive(Deserialize, TS)]
+#[ts(export)]
+pub struct MergeFieldEventType {
+    pub into_type_id: i32,
+}
+
+#[derive(Serialize, TS)]
+#[ts(export)]
+pub struct MergedFieldEventType {
+    pub type_id: i32,
+    /// Number of field events moved to `type_id`.
+    pub moved_events: i64,
+}
+
+#[derive(Deserialize)]
+struct FieldEventTypeParams {
+    farm_id: Option<i32>,
+    /// Include archived types, which are left out by default.
+    #[serde(default)]
+    include_archived: bool,
+}
+
+const WHAT: &str = "field event type";
+
+/// Id of the type for a field event on `field_id`, either the given type or
+/// the farm's type named `name`, which is created if the farm doesn't have it.
+/// Runs on the caller's transaction so a new type is rolled back with the event.
+pub async fn resolve_event_type(
+    conn: &mut PgConnection,
+    field_id: i32,
+    type_id: Option<i32>,
+    name: Option<&str>,
+    user: &str,
+) -> Result<i32, SorjordetError> {
+    let farm_id = query_scalar!("SELECT farm_id FROM farm_field WHERE id = $1", field_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| SorjordetError::InvalidInput(format!("field {field_id} not found")))?;
+
+    let event_type = match (type_id, name) {
+        (Some(type_id), _) => query!(
+            "SELECT id, farm_id, archived FROM field_event_type WHERE id = $1",
+            type_id
+        )
+        .fetch_optional(&mut *conn)
+        .await?
+        .map(|x| (x.id, x.farm_id, x.archived)),
+        (None, Some(name)) => query!(
+            "INSERT INTO field_event_type (name, farm_id, created_by, updated_by)
+                    VALUES ($1, $2, $3, $3)
+                    ON CONFLICT (farm_id, LOWER(name)) DO UPDATE SET name = field_event_type.name
+                    RETURNING id, farm_id, archived
+                ",
+            name.trim(),
+            farm_id,
+            user
+        )
+        .fetch_optional(&mut *conn)
+        .await?
+        .map(|x| (x.id, x.farm_id, x.archived)),
+        (None, None) => None,
+    };
+
+    match event_type {
+        None => Err(SorjordetError::InvalidInput(
+            "field event type not found".to_string(),
+        )),
+        Some((_, type_farm_id, _)) if type_farm_id != farm_id => Err(SorjordetError::InvalidInput(
+            "field event type belongs to another farm than the field".to_string(),
+        )),
+        Some((id, _, true)) => Err(SorjordetError::InvalidInput(format!(
+            "field event type with id {id} is archived"
+        ))),
+        Some((id, _, false)) => Ok(id),
+    }
+}
+
+async fn get_types(
+    State(pool): State<PgPool>,
+    extract::Query(params): extract::Query<FieldEventTypeParams>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    let result: Vec<FieldEventType> = query_as!(
+        FieldEventType,
+        "SELECT id, name, farm_id, archived, created_at, created_by, updated_at, updated_by
+                FROM field_event_type
+                WHERE ($1::int IS NULL OR farm_id = $1)
+                    AND ($2 OR NOT archived)
+                ORDER BY name
+            ",
+        params.farm_id,
+        params.include_archived
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(result))
+}
+
+async fn post_type(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    ValidatedJson(payload): ValidatedJson<CreateFieldEventType>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    let result = query_scalar!(
+        "INSERT INTO field_event_type (name, farm_id, created_by, updated_by)
+                VALUES ($1, $2, $3, $3)
+                RETURNING id
+            ",
+        payload.name.trim(),
+        payload.farm_id,
+        &claims.sub
+    )
+    .fetch_one(&pool)
+    .await
+    .map_err(insert_error(WHAT, payload.farm_id))?;
+
+    tracing::info!("new field_event_type inserted by {}", claims.sub);
+
+    Ok(Json(result))
+}
+
+async fn patch_type(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    extract::Path(type_id): extract::Path<i32>,
+    ValidatedJson(payload): ValidatedJson<UpdateFieldEventType>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    let result = query!(
+        "UPDATE field_event_type
+                SET name = COALESCE($1, name),
+                    archived = COALESCE($2, archived),
+                    updated_at = NOW(),
+                    updated_by = $4
+                WHERE id = $3
+            ",
+        payload.name.value().map(|x| x.trim()),
+        payload.archived.value(),
+        &type_id,
+        &claims.sub
+    )
+    .execute(&pool)
+    .await
+    .map_err(name_conflict(WHAT))?;
+
+    if result.rows_affected() == 0 {
+        tracing::info!("field_event_type {} not found", type_id);
+        return Err(SorjordetError::NotFound(format!(
+            "field_event_type with id {} not found",
+            type_id
+        )));
+    }
+
+    tracing::info!("field_event_type {type_id} updated by {}", claims.sub);
+
+    Ok(())
+}
+
+async fn merge_type(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    extract::Path(type_id): extract::Path<i32>,
+    Json(payload): Json<MergeFieldEventType>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    let into_type_id = payload.into_type_id;
+    let mut tx = pool.begin().await?;
+
+    let types = query!(
+        "SELECT id, farm_id
+                FROM field_event_type
+                WHERE id = $1 OR id = $2
+                ORDER BY id
+                FOR UPDATE
+            ",
+        type_id,
+        into_type_id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let farm_of = |id| types.iter().find(|x| x.id == id).map(|x| x.farm_id);
+    check_merge(
+        WHAT,
+        type_id,
+        into_type_id,
+        farm_of(type_id),
+        farm_of(into_type_id),
+    )?;
+
+    let moved = query!(
+        "UPDATE field_event
+                SET type_id = $2,
+                    version = version + 1,
+                    updated_at = NOW(),
+                    updated_by = $3
+                WHERE type_id = $1
+            ",
+        type_id,
+        into_type_id,
+        &claims.sub
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    query!("DELETE FROM field_event_type WHERE id = $1", type_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    tracing::info!(
+        "field_event_type {type_id} merged into {into_type_id} by {}",
+        claims.sub
+    );
+
+    Ok(Json(MergedFieldEventType {
+        type_id: into_type_id,
+        moved_events: moved.rows_affected() as i64,
+    }))
+}
+
+/// Deletes a type no field event has, others can be merged or archived instead.
+async fn delete_type(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    extract::Path(type_id): extract::Path<i32>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    let mut tx = pool.begin().await?;
+
+    // the lock keeps new events of the type out until it is gone
+    let Some(used) = query_scalar!(
+        r#"SELECT (SELECT COUNT(*) FROM field_event WHERE type_id = $1) as "used!"
+                FROM field_event_type
+                WHERE id = $1
+                FOR UPDATE
+            "#,
+        type_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    else {
+        return Err(SorjordetError::NotFound(format!(
+            "field_event_type with id {} not found",
+            type_id
+        )));
+    };
+    if used > 0 {
+        return Err(SorjordetError::Conflict(format!(
+            "field_event_type with id {type_id} is used by {used} field events, \
+            merge or archive it instead"
+        )));
+    }
+
+    query!("DELETE FROM field_event_type WHERE id = $1", type_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    tracing::info!("field_event_type {type_id} deleted by {}", claims.sub);
+
+    Ok(())
+}
+
+pub fn field_event_type_router() -> Router<PgPool> {
+    Router::new()
+        .route("/{type_id}/merge", post(merge_type))
+        .route("/{type_id}", patch(patch_type).delete(delete_type))
+        .route("/", get(get_types).post(post_type))
+}
@@ -0,0 +1,866 @@
+use std::collections::BTreeMap;
+use std::io::{Cursor, Read};
+
+use axum::{
+    self, Json, Router,
+    body::Bytes,
+    extract::{self, DefaultBodyLimit, State},
+    response::IntoResponse,
+    routing::post,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{PgPool, query, query_scalar};
+use ts_rs::TS;
+
+use crate::auth::Claims;
+use crate::errors::SorjordetError;
+
+use super::farm_field::{
+    FieldOverlap, check_overlaps, ensure_names_free, field_save_error, find_overlaps, taken_names,
+};
+use super::validation::{Validate, ValidatedJson, validate_geojson, validate_name};
+
+/// Parcel exports are larger than the default request body limit.
+const MAX_IMPORT_BYTES: usize = 32 * 1024 * 1024;
+
+/// Limit on the files extracted from a zip, together.
+const MAX_UNZIPPED_BYTES: u64 = 128 * 1024 * 1024;
+
+/// Attributes tried, case insensitively, for the field name unless one is given.
+const NAME_ATTRIBUTES: [&str; 3] = ["name", "navn", "namn"];
+
+#[derive(Deserialize, Serialize, TS, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum ImportFormat {
+    /// Zip with the `.shp`, `.dbf` and optionally `.prj` files.
+    Shapefile,
+    /// KML, or KMZ as saved by Google Earth.
+    Kml,
+}
+
+#[derive(Deserialize)]
+struct PreviewParams {
+    format: ImportFormat,
+    /// Farm the features are compared against for overlaps.
+    farm_id: i32,
+    /// Attribute used as the field name.
+    name_attribute: Option<String>,
+}
+
+/// A feature of an import file as it would become a field.
+#[derive(Serialize, TS)]
+#[ts(export)]
+pub struct ImportFeature {
+    /// Position of the feature in the file.
+    pub index: i32,
+    pub name: String,
+    /// Attributes of the feature, from the `.dbf` or the KML's extended data.
+    pub properties: BTreeMap<String, String>,
+    /// Boundary as a GeoJSON Feature in EPSG:3857, as accepted by `POST /farm_fields`.
+    pub map_polygon_string: String,
+    pub area_m2: f64,
+    /// Existing fields of the farm the feature overlaps.
+    pub overlaps: Vec<FieldOverlap>,
+    /// The name is used by an active field or another feature of the file,
+    /// the feature must be renamed to be imported.
+    pub name_taken: bool,
+}
+
+#[derive(Serialize, TS)]
+#[ts(export)]
+pub struct ImportPreview {
+    /// Coordinate system the features were reprojected from,
+    /// `None` when they were in WGS84 already.
+    pub source_crs: Option<String>,
+    pub features: Vec<ImportFeature>,
+    /// Names of features without any polygon area, which can't become fields.
+    pub skipped: Vec<String>,
+}
+
+#[derive(Deserialize, TS)]
+#[ts(export)]
+pub struct ImportedField {
+    pub name: String,
+    pub map_polygon_string: String,
+}
+
+/// Previewed features to create as fields, possibly renamed or left out.
+#[derive(Deserialize, TS)]
+#[ts(export)]
+pub struct ImportFarmFields {
+    pub farm_id: i32,
+    pub farm_field_group_id: Option<i32>,
+    pub fields: Vec<ImportedField>,
+}
+
+impl Validate for ImportFarmFields {
+    fn validate(&self) -> Result<(), SorjordetError> {
+        if self.fields.is_empty() {
+            return Err(SorjordetError::InvalidInput(
+                "fields must not be empty".to_string(),
+            ));
+        }
+        for (i, field) in self.fields.iter().enumerate() {
+            validate_name("name", &field.name, 256)?;
+            validate_geojson("map_polygon_string", &field.map_polygon_string)?;
+            if self.fields[..i]
+                .iter()
+                .any(|x| x.name.trim() == field.name.trim())
+            {
+                return Err(SorjordetError::InvalidInput(format!(
+                    "fields contains {} more than once",
+                    field.name.trim()
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A feature read from an import file, its geometry a GeoJSON MultiPolygon
+/// in the file's coordinate system.
+struct ParsedFeature {
+    name: Option<String>,
+    properties: BTreeMap<String, String>,
+    geometry: Option<serde_json::Value>,
+}
+
+struct ParsedFile {
+    /// WKT from the `.prj`, `None` for WGS84.
+    crs: Option<String>,
+    features: Vec<ParsedFeature>,
+}
+
+pub fn invalid_file(reason: impl std::fmt::Display) -> SorjordetError {
+    SorjordetError::InvalidInput(format!("could not read the import file: {reason}"))
+}
+
+/// Files with one of the `extensions` in a zip archive, by lower case name.
+/// Other entries aren't extracted, and the extracted files are capped in size
+/// since a small archive can unpack to far more than the upload limit.
+fn unzip(data: &[u8], extensions: &[&str]) -> Result<BTreeMap<String, Vec<u8>>, SorjordetError> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data)).map_err(invalid_file)?;
+    let mut files = BTreeMap::new();
+    let mut remaining = MAX_UNZIPPED_BYTES;
+    for i in 0..archive.len() {
+        let file = archive.by_index(i).map_err(invalid_file)?;
+        let name = file.name().to_lowercase();
+        let wanted = extensions
+            .iter()
+            .any(|x| name.rsplit_once('.').is_some_and(|(_, ext)| ext == *x));
+        if file.is_dir() || !wanted {
+            continue;
+        }
+        if file.size() > remaining {
+            return Err(invalid_file("the zip unpacks to too much data"));
+        }
+        // the size in the archive can't be trusted, so read at most one byte more
+        let mut content = Vec::new();
+        file.take(remaining + 1)
+            .read_to_end(&mut content)
+            .map_err(invalid_file)?;
+        remaining = remaining
+            .checked_sub(content.len() as u64)
+            .ok_or_else(|| invalid_file("the zip unpacks to too much data"))?;
+        files.insert(name, content);
+    }
+    Ok(files)
+}
+
+/// UTF-8, falling back to Latin-1 which older exports use.
+pub fn decode_text(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => bytes.iter().map(|&x| x as char).collect(),
+    }
+}
+
+fn be_i32(data: &[u8], at: usize) -> Option<i32> {
+    Some(i32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn le_i32(data: &[u8], at: usize) -> Option<i32> {
+    Some(i32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn le_u16(data: &[u8], at: usize) -> Option<usize> {
+    Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?) as usize)
+}
+
+fn le_f64(data: &[u8], at: usize) -> Option<f64> {
+    Some(f64::from_le_bytes(data.get(at..at + 8)?.try_into().ok()?))
+}
+
+/// Twice the signed area of a ring, negative when it runs clockwise.
+fn ring_area(ring: &[[f64; 2]]) -> f64 {
+    ring.windows(2)
+        .map(|x| x[0][0] * x[1][1] - x[1][0] * x[0][1])
+        .sum()
+}
+
+/// Polygon geometry of a Shapefile record, `None` for null and non-polygon shapes.
+fn shp_polygon(content: &[u8]) -> Option<serde_json::Value> {
+    // Polygon, PolygonZ and PolygonM share the layout up to the points
+    if !matches!(le_i32(content, 0)?, 5 | 15 | 25) {
+        return None;
+    }
+    let part_count = usize::try_from(le_i32(content, 36)?).ok()?;
+    let point_count = usize::try_from(le_i32(content, 40)?).ok()?;
+    let points_at = 44 + 4 * part_count;
+
+    let mut starts = (0..part_count)
+        .map(|i| usize::try_from(le_i32(content, 44 + 4 * i)?).ok())
+        .collect::<Option<Vec<_>>>()?;
+    starts.push(point_count);
+
+    // outer rings run clockwise and are followed by their holes
+    let mut polygons: Vec<Vec<Vec<[f64; 2]>>> = Vec::new();
+    for part in starts.windows(2) {
+        let ring = (part[0]..part[1])
+            .map(|i| {
+                let at = points_at + 16 * i;
+                Some([le_f64(content, at)?, le_f64(content, at + 8)?])
+            })
+            .collect::<Option<Vec<_>>>()?;
+        if ring.len() < 4 {
+            continue;
+        }
+        match polygons.last_mut() {
+            Some(polygon) if ring_area(&ring) > 0.0 => polygon.push(ring),
+            _ => polygons.push(vec![ring]),
+        }
+    }
+
+    if polygons.is_empty() {
+        return None;
+    }
+    Some(json!({ "type": "MultiPolygon", "coordinates": polygons }))
+}
+
+fn read_shp(data: &[u8]) -> Result<Vec<Option<serde_json::Value>>, SorjordetError> {
+    if be_i32(data, 0) != Some(9994) {
+        return Err(invalid_file("not a .shp file"));
+    }
+
+    let mut shapes = Vec::new();
+    let mut at = 100;
+    while let Some(words) = be_i32(data, at + 4) {
+        let end = at + 8 + 2 * usize::try_from(words).map_err(|_| invalid_file("bad record"))?;
+        let content = data
+            .get(at + 8..end)
+            .ok_or_else(|| invalid_file("truncated .shp file"))?;
+        shapes.push(shp_polygon(content));
+        at = end;
+    }
+    Ok(shapes)
+}
+
+/// Attributes of each record in a `.dbf` file.
+fn read_dbf(data: &[u8]) -> Result<Vec<BTreeMap<String, String>>, SorjordetError> {
+    let header = || invalid_file("bad .dbf header");
+    let record_count =
+        usize::try_from(le_i32(data, 4).ok_or_else(header)?).map_err(|_| header())?;
+    let header_len = le_u16(data, 8).ok_or_else(header)?;
+    let record_len = le_u16(data, 10).ok_or_else(header)?;
+    // every record has at least its deletion flag, so the count is bounded by the file size
+    let records_end = record_count
+        .checked_mul(record_len)
+        .and_then(|x| x.checked_add(header_len));
+    if record_len == 0 || records_end.is_none_or(|x| x > data.len()) {
+        return Err(invalid_file("truncated .dbf file"));
+    }
+
+    let mut columns = Vec::new();
+    let mut at = 32;
+    while at + 32 <= header_len && data.get(at) != Some(&0x0d) {
+        let descriptor = data.get(at..at + 32).ok_or_else(header)?;
+        let name_len = descriptor[..11].iter().position(|&x| x == 0).unwrap_or(11);
+        columns.push((
+            decode_text(&descriptor[..name_len]),
+            descriptor[16] as usize,
+        ));
+        at += 32;
+    }
+
+    (0..record_count)
+        .map(|i| {
+            let start = header_len + i * record_len;
+            let record = data
+                .get(start..start + record_len)
+                .ok_or_else(|| invalid_file("truncated .dbf file"))?;
+            // the first byte flags deleted records, which still match a shape
+            let mut offset = 1;
+            let mut values = BTreeMap::new();
+            for (name, len) in &columns {
+                let value = record.get(offset..offset + len).unwrap_or_default();
+                values.insert(name.clone(), decode_text(value).trim().to_string());
+                offset += len;
+            }
+            Ok(values)
+        })
+        .collect()
+}
+
+fn read_shapefile(data: &[u8]) -> Result<ParsedFile, SorjordetError> {
+    let files = unzip(data, &["shp", "shx", "dbf", "prj"])?;
+    let Some((shp_name, shp)) = files.iter().find(|(name, _)| name.ends_with(".shp")) else {
+        return Err(invalid_file("the zip has no .shp file"));
+    };
+    let stem = shp_name.trim_end_matches(".shp");
+
+    let shapes = read_shp(shp)?;
+    let records = match files.get(&format!("{stem}.dbf")) {
+        Some(dbf) => read_dbf(dbf)?,
+        None => Vec::new(),
+    };
+    let crs = files
+        .get(&format!("{stem}.prj"))
+        .map(|x| decode_text(x).trim().to_string())
+        .filter(|x| !x.is_empty());
+
+    let features = shapes
+        .into_iter()
+        .enumerate()
+        .map(|(i, geometry)| ParsedFeature {
+            name: None,
+            properties: records.get(i).cloned().unwrap_or_default(),
+            geometry,
+        })
+        .collect();
+
+    Ok(ParsedFile { crs, features })
+}
+
+/// Opening tag attributes and content of each `<tag>` element in `xml`,
+/// ignoring namespace prefixes. Elements of the same tag are not nested in KML.
+pub fn elements<'a>(xml: &'a str, tag: &str) -> Vec<(&'a str, &'a str)> {
+    let mut found = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        let name_end = rest
+            .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+            .unwrap_or(rest.len());
+        let name = &rest[..name_end];
+        let local = name.rsplit(':').next().unwrap_or(name);
+        if local != tag {
+            continue;
+        }
+        let Some(open_end) = rest.find('>') else {
+            break;
+        };
+        let attributes = &rest[name_end..open_end];
+        if attributes.ends_with('/') {
+            continue;
+        }
+        let body = &rest[open_end + 1..];
+        let close = format!("</{name}>");
+        let Some(body_end) = body.find(&close) else {
+            break;
+        };
+        found.push((attributes, &body[..body_end]));
+        rest = &body[body_end + close.len()..];
+    }
+    found
+}
+
+/// Value of the attribute `name` among the attributes of a start tag,
+/// quoted with either `"` or `'`.
+pub fn attribute(attributes: &str, name: &str) -> Option<String> {
+    let mut rest = attributes;
+    loop {
+        let (key, value) = rest.split_once('=')?;
+        let value = value.trim_start();
+        let quote = value.chars().next().filter(|x| *x == '"' || *x == '\'')?;
+        let end = value[1..].find(quote)? + 1;
+        if key.trim() == name {
+            return Some(xml_text(&value[1..end]));
+        }
+        rest = &value[end + 1..];
+    }
+}
+
+pub fn xml_text(text: &str) -> String {
+    let text = text.trim();
+    if let Some(data) = text
+        .strip_prefix("<![CDATA[")
+        .and_then(|x| x.strip_suffix("]]>"))
+    {
+        return data.trim().to_string();
+    }
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// `lon,lat[,alt]` tuples of a KML `<coordinates>` element.
+fn kml_ring(boundary: &str) -> Option<Vec<[f64; 2]>> {
+    let (_, coordinates) = elements(boundary, "coordinates").into_iter().next()?;
+    let ring = coordinates
+        .split_whitespace()
+        .map(|tuple| {
+            let mut values = tuple.split(',').map(|x| x.parse::<f64>().ok());
+            Some([values.next()??, values.next()??])
+        })
+        .collect::<Option<Vec<_>>>()?;
+    (ring.len() >= 4).then_some(ring)
+}
+
+fn read_kml(data: &[u8]) -> Result<ParsedFile, SorjordetError> {
+    let kml = if data.starts_with(b"PK") {
+        let files = unzip(data, &["kml"])?;
+        let Some(kml) = files.iter().find(|(name, _)| name.ends_with(".kml")) else {
+            return Err(invalid_file("the KMZ has no .kml file"));
+        };
+        decode_text(kml.1)
+    } else {
+        decode_text(data)
+    };
+
+    let features = elements(&kml, "Placemark")
+        .into_iter()
+        .map(|(_, placemark)| {
+            let name = elements(placemark, "name")
+                .first()
+                .map(|(_, x)| xml_text(x))
+                .filter(|x| !x.is_empty());
+
+            let mut properties = BTreeMap::new();
+            for (attributes, data) in elements(placemark, "Data") {
+                if let (Some(key), Some((_, value))) = (
+                    attribute(attributes, "name"),
+                    elements(data, "value").first(),
+                ) {
+                    properties.insert(key, xml_text(value));
+                }
+            }
+            for (attributes, value) in elements(placemark, "SimpleData") {
+                if let Some(key) = attribute(attributes, "name") {
+                    properties.insert(key, xml_text(value));
+                }
+            }
+
+            let polygons: Vec<Vec<Vec<[f64; 2]>>> = elements(placemark, "Polygon")
+                .into_iter()
+                .filter_map(|(_, polygon)| {
+                    let (_, outer) = elements(polygon, "outerBoundaryIs").into_iter().next()?;
+                    let mut rings = vec![kml_ring(outer)?];
+                    rings.extend(
+                        elements(polygon, "innerBoundaryIs")
+                            .into_iter()
+                            .filter_map(|(_, inner)| kml_ring(inner)),
+                    );
+                    Some(rings)
+                })
+                .collect();
+
+            ParsedFeature {
+                name,
+                properties,
+                geometry: (!polygons.is_empty())
+                    .then(|| json!({ "type": "MultiPolygon", "coordinates": polygons })),
+            }
+        })
+        .collect();
+
+    Ok(ParsedFile {
+        crs: None,
+        features,
+    })
+}
+
+/// Name of a feature, from `name_attribute` when given.
+fn feature_name(feature: &ParsedFeature, name_attribute: Option<&str>, index: usize) -> String {
+    let from_attribute = match name_attribute {
+        Some(attribute) => feature.properties.get(attribute),
+        None => feature
+            .properties
+            .iter()
+            .find(|(key, _)| NAME_ATTRIBUTES.contains(&key.to_lowercase().as_str()))
+            .map(|(_, value)| value),
+    };
+
+    from_attribute
+        .filter(|x| !x.is_empty())
+        .or(feature.name.as_ref())
+        .cloned()
+        .unwrap_or_else(|| format!("Field {}", index + 1))
+}
+
+/// Reads an import file and shows the fields it would create, without saving anything.
+async fn preview_import(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    extract::Query(params): extract::Query<PreviewParams>,
+    body: Bytes,
+) -> Result<impl IntoResponse, SorjordetError> {
+    let parsed = match params.format {
+        ImportFormat::Shapefile => read_shapefile(&body)?,
+        ImportFormat::Kml => read_kml(&body)?,
+    };
+
+    tracing::info!(
+        "import preview of {} features by {}",
+        parsed.features.len(),
+        claims.sub
+    );
+
+    let names: Vec<String> = parsed
+        .features
+        .iter()
+        .enumerate()
+        .map(|(i, x)| {
+            feature_name(x, params.name_attribute.as_deref(), i)
+                .trim()
+                .to_string()
+        })
+        .collect();
+    let taken = taken_names(&pool, &names).await?;
+    let geometries: Vec<Option<String>> = parsed
+        .features
+        .iter()
+        .map(|x| x.geometry.as_ref().map(|x| x.to_string()))
+        .collect();
+
+    let rows = query!(
+        r#"SELECT g.index::int as "index!", field_boundary_geojson(b.boundary) as map_polygon_string,
+                    ST_Area(b.boundary::geography) as area_m2
+                FROM UNNEST($1::text[]) WITH ORDINALITY AS g(geojson, index)
+                    CROSS JOIN LATERAL (
+                        SELECT field_boundary_import(g.geojson, $2) AS boundary
+                    ) b
+                ORDER BY g.index
+            "#,
+        &geometries as &[Option<String>],
+        parsed.crs
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|err| match &err {
+        sqlx::Error::Database(db) => invalid_file(db.message()),
+        _ => err.into(),
+    })?;
+
+    let mut features = Vec::new();
+    let mut skipped = Vec::new();
+    for (row, (feature, name)) in rows.into_iter().zip(parsed.features.into_iter().zip(names)) {
+        let (Some(map_polygon_string), Some(area_m2)) = (row.map_polygon_string, row.area_m2)
+        else {
+            skipped.push(name);
+            continue;
+        };
+        if area_m2 <= 0.0 {
+            skipped.push(name);
+            continue;
+        }
+        let overlaps = find_overlaps(&pool, params.farm_id, &map_polygon_string, None).await?;
+        features.push(ImportFeature {
+            index: row.index - 1,
+            name_taken: taken.contains(&name),
+            name,
+            properties: feature.properties,
+            map_polygon_string,
+            area_m2,
+            overlaps,
+        });
+    }
+    // repeated names in the file, parcel exports often have them
+    for i in 0..features.len() {
+        if features
+            .iter()
+            .filter(|x| x.name == features[i].name)
+            .count()
+            > 1
+        {
+            features[i].name_taken = true;
+        }
+    }
+
+    Ok(Json(ImportPreview {
+        source_crs: parsed.crs,
+        features,
+        skipped,
+    }))
+}
+
+/// Creates the fields from a preview, all of them or none.
+async fn post_import(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    ValidatedJson(payload): ValidatedJson<ImportFarmFields>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    let names: Vec<String> = payload
+        .fields
+        .iter()
+        .map(|x| x.name.trim().to_string())
+        .collect();
+
+    let mut tx = pool.begin().await?;
+    ensure_names_free(&mut *tx, &names).await?;
+    let mut ids = Vec::with_capacity(payload.fields.len());
+    for field in &payload.fields {
+        // the fields inserted so far are checked against too
+        check_overlaps(&mut tx, payload.farm_id, &field.map_polygon_string, None).await?;
+        let id = query_scalar!(
+            "INSERT INTO farm_field
+                        (name, farm_id, farm_field_group_id, boundary, created_by, updated_by)
+                    VALUES ($1, $2, $3, field_boundary_from_geojson($4), $5, $5)
+                    RETURNING id
+                ",
+            field.name.trim(),
+            payload.farm_id,
+            payload.farm_field_group_id,
+            &field.map_polygon_string,
+            &claims.sub
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(field_save_error)?;
+        ids.push(id);
+    }
+    tx.commit().await?;
+
+    tracing::info!("{} imported fields inserted by {}", ids.len(), claims.sub);
+
+    Ok(Json(ids))
+}
+
+pub fn field_import_router() -> Router<PgPool> {
+    Router::new()
+        .route(
+            "/import/preview",
+            post(preview_import).layer(DefaultBodyLimit::max(MAX_IMPORT_BYTES)),
+        )
+        .route("/import", post(post_import))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    /// A `.shp` file with a polygon record per entry, each given as its rings.
+    fn shp(shapes: &[Vec<Vec<[f64; 2]>>]) -> Vec<u8> {
+        let mut data = vec![0; 100];
+        data[..4].copy_from_slice(&9994i32.to_be_bytes());
+        for (i, rings) in shapes.iter().enumerate() {
+            let points: Vec<&[f64; 2]> = rings.iter().flatten().collect();
+            let mut content = Vec::new();
+            content.extend(5i32.to_le_bytes());
+            content.extend([0; 32]);
+            content.extend((rings.len() as i32).to_le_bytes());
+            content.extend((points.len() as i32).to_le_bytes());
+            let mut start = 0;
+            for ring in rings {
+                content.extend((start as i32).to_le_bytes());
+                start += ring.len();
+            }
+            for point in points {
+                content.extend(point[0].to_le_bytes());
+                content.extend(point[1].to_le_bytes());
+            }
+            data.extend((i as i32 + 1).to_be_bytes());
+            data.extend((content.len() as i32 / 2).to_be_bytes());
+            data.extend(content);
+        }
+        data
+    }
+
+    /// A `.dbf` file with character columns of the given widths.
+    fn dbf(columns: &[(&str, u8)], records: &[Vec<&[u8]>]) -> Vec<u8> {
+        let header_len = 32 + 32 * columns.len() + 1;
+        let record_len = 1 + columns.iter().map(|(_, len)| *len as usize).sum::<usize>();
+        let mut data = vec![3, 126, 1, 1];
+        data.extend((records.len() as i32).to_le_bytes());
+        data.extend((header_len as u16).to_le_bytes());
+        data.extend((record_len as u16).to_le_bytes());
+        data.extend([0; 20]);
+        for (name, len) in columns {
+            let mut descriptor = [0; 32];
+            descriptor[..name.len()].copy_from_slice(name.as_bytes());
+            descriptor[11] = b'C';
+            descriptor[16] = *len;
+            data.extend(descriptor);
+        }
+        data.push(0x0d);
+        for record in records {
+            data.push(b' ');
+            for ((_, len), value) in columns.iter().zip(record) {
+                let mut field = vec![b' '; *len as usize];
+                field[..value.len()].copy_from_slice(value);
+                data.extend(field);
+            }
+        }
+        data
+    }
+
+    fn square(x: f64, y: f64, size: f64, clockwise: bool) -> Vec<[f64; 2]> {
+        let mut ring = vec![
+            [x, y],
+            [x + size, y],
+            [x + size, y + size],
+            [x, y + size],
+            [x, y],
+        ];
+        if clockwise {
+            ring.reverse();
+        }
+        ring
+    }
+
+    #[test]
+    fn shp_polygon_with_hole() {
+        let outer = square(0.0, 0.0, 10.0, true);
+        let hole = square(2.0, 2.0, 2.0, false);
+        let shapes = read_shp(&shp(&[vec![outer.clone(), hole.clone()]])).unwrap();
+
+        assert_eq!(
+            shapes,
+            vec![Some(
+                json!({ "type": "MultiPolygon", "coordinates": [[outer, hole]] })
+            )]
+        );
+    }
+
+    #[test]
+    fn shp_multipart_shape() {
+        let first = square(0.0, 0.0, 10.0, true);
+        let second = square(20.0, 0.0, 5.0, true);
+        let shapes = read_shp(&shp(&[vec![first.clone(), second.clone()]])).unwrap();
+
+        assert_eq!(
+            shapes,
+            vec![Some(
+                json!({ "type": "MultiPolygon", "coordinates": [[first], [second]] })
+            )]
+        );
+    }
+
+    #[test]
+    fn shp_truncated() {
+        let data = shp(&[vec![square(0.0, 0.0, 10.0, true)]]);
+
+        assert!(read_shp(&data[..data.len() - 8]).is_err());
+        assert!(read_shp(&data[..50]).unwrap().is_empty());
+        assert!(read_shp(&data[..2]).is_err());
+    }
+
+    #[test]
+    fn dbf_latin1_text() {
+        let data = dbf(
+            &[("NAVN", 16), ("AREAL", 8)],
+            &[vec![b"S\xf8rjordet", b"12.5"], vec![b"\xc5ker", b""]],
+        );
+        let records = read_dbf(&data).unwrap();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["NAVN"], "Sørjordet");
+        assert_eq!(records[0]["AREAL"], "12.5");
+        assert_eq!(records[1]["NAVN"], "Åker");
+        assert_eq!(records[1]["AREAL"], "");
+    }
+
+    #[test]
+    fn dbf_truncated() {
+        let data = dbf(&[("NAVN", 16)], &[vec![b"Jordet"]]);
+
+        // records cut off
+        assert!(read_dbf(&data[..data.len() - 4]).is_err());
+        // column descriptors cut off while the header claims they are there
+        assert!(read_dbf(&data[..40]).is_err());
+        assert!(read_dbf(&data[..6]).is_err());
+
+        let mut huge_count = data.clone();
+        huge_count[4..8].copy_from_slice(&i32::MAX.to_le_bytes());
+        assert!(read_dbf(&huge_count).is_err());
+    }
+
+    #[test]
+    fn xml_elements() {
+        let xml = r#"<kml:Document><name>A &amp; B</name><Data name="x"/>
+            <Data name="navn"><value><![CDATA[Øvre <jorde>]]></value></Data>
+            <name>second</name></kml:Document>"#;
+
+        let names: Vec<String> = elements(xml, "name")
+            .into_iter()
+            .map(|(_, x)| xml_text(x))
+            .collect();
+        assert_eq!(names, ["A & B", "second"]);
+
+        let data = elements(xml, "Data");
+        assert_eq!(data.len(), 1);
+        assert_eq!(attribute(data[0].0, "name").as_deref(), Some("navn"));
+        assert_eq!(xml_text(elements(data[0].1, "value")[0].1), "Øvre <jorde>");
+
+        assert_eq!(elements(xml, "Document").len(), 1);
+        assert!(elements("<name>unclosed", "name").is_empty());
+    }
+
+    #[test]
+    fn attribute_quotes() {
+        let attributes = r#" xlat="1" lat = '59.5' lon="10.25" note='say "hi"'"#;
+        assert_eq!(attribute(attributes, "lat").as_deref(), Some("59.5"));
+        assert_eq!(attribute(attributes, "lon").as_deref(), Some("10.25"));
+        assert_eq!(
+            attribute(attributes, "note").as_deref(),
+            Some(r#"say "hi""#)
+        );
+        assert_eq!(attribute(attributes, "ele"), None);
+        assert_eq!(attribute(r#" lat=59.5"#, "lat"), None);
+    }
+
+    #[test]
+    fn kml_polygon_with_hole() {
+        let kml = br#"<?xml version="1.0"?><kml><Document><Placemark>
+            <name>Jordet</name>
+            <ExtendedData><SchemaData><SimpleData name="owner">Leid</SimpleData></SchemaData></ExtendedData>
+            <Polygon>
+                <outerBoundaryIs><LinearRing><coordinates>
+                    0,0,0 10,0,0 10,10,0 0,10,0 0,0,0
+                </coordinates></LinearRing></outerBoundaryIs>
+                <innerBoundaryIs><LinearRing><coordinates>
+                    2,2 4,2 4,4 2,4 2,2
+                </coordinates></LinearRing></innerBoundaryIs>
+            </Polygon>
+        </Placemark></Document></kml>"#;
+        let parsed = read_kml(kml).unwrap();
+
+        assert_eq!(parsed.features.len(), 1);
+        let feature = &parsed.features[0];
+        assert_eq!(feature.name.as_deref(), Some("Jordet"));
+        assert_eq!(feature.properties["owner"], "Leid");
+        let coordinates = &feature.geometry.as_ref().unwrap()["coordinates"];
+        assert_eq!(coordinates[0].as_array().unwrap().len(), 2);
+        assert_eq!(coordinates[0][1][1], json!([4.0, 2.0]));
+    }
+
+    #[test]
+    fn unzip_skips_other_files() {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default();
+        for (name, content) in [
+            ("Jorder.SHP", "shp"),
+            ("jorder.cpg", "UTF-8"),
+            ("x.dbf", "dbf"),
+        ] {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        let data = zip.finish().unwrap().into_inner();
+
+        let files = unzip(&data, &["shp", "dbf"]).unwrap();
+        assert_eq!(
+            files.into_iter().collect::<Vec<_>>(),
+            [
+                ("jorder.shp".to_string(), b"shp".to_vec()),
+                ("x.dbf".to_string(), b"dbf".to_vec())
+            ]
+        );
+        assert!(unzip(b"PK not a zip", &["kml"]).is_err());
+    }
+}
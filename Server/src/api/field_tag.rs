@@ -0,0 +1,375 @@
+use axum::{
+    self, Json, Router,
+    extract::{self, State},
+    response::IntoResponse,
+    routing::{get, patch, put},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool, query, query_as, query_scalar};
+use ts_rs::TS;
+
+use crate::auth::Claims;
+use crate::errors::SorjordetError;
+
+use super::catalogue::{insert_error, name_conflict};
+use super::etag::{IfMatch, etag, not_found_or_conflict};
+use super::patch::Patch;
+use super::validation::{Validate, ValidatedJson, validate_color, validate_name};
+
+/// A label on fields. Unlike groups a field can have any number of tags.
+#[derive(Serialize, Deserialize, FromRow, TS)]
+#[ts(export)]
+pub struct FieldTag {
+    pub id: i32,
+    pub farm_id: i32,
+    pub name: String,
+    /// What the tag groups fields by, like soil type or ownership.
+    pub category: Option<String>,
+    pub draw_color: Option<String>,
+    /// Fields with the tag, leaving out retired fields.
+    pub fields: Vec<i32>,
+    pub version: i32,
+    pub created_at: DateTime<Utc>,
+    pub created_by: Option<String>,
+    pub updated_at: DateTime<Utc>,
+    pub updated_by: Option<String>,
+}
+
+#[derive(Deserialize, TS)]
+#[ts(export)]
+pub struct CreateFieldTag {
+    pub farm_id: i32,
+    pub name: String,
+    pub category: Option<String>,
+    pub draw_color: Option<String>,
+}
+
+impl Validate for CreateFieldTag {
+    fn validate(&self) -> Result<(), SorjordetError> {
+        validate_name("name", &self.name, 128)?;
+        if let Some(category) = &self.category {
+            validate_name("category", category, 128)?;
+        }
+        if let Some(color) = &self.draw_color {
+            validate_color("draw_color", color, 64)?;
+        }
+        Ok(())
+    }
+}
+
+/// JSON merge patch for a tag, only the given members are changed.
+#[derive(Deserialize, TS)]
+#[ts(export)]
+pub struct UpdateFieldTag {
+    #[serde(default)]
+    #[ts(as = "Option<String>", optional)]
+    pub name: Patch<String>,
+    #[serde(default)]
+    #[ts(as = "Option<String>", optional = nullable)]
+    pub category: Patch<String>,
+    #[serde(default)]
+    #[ts(as = "Option<String>", optional = nullable)]
+    pub draw_color: Patch<String>,
+}
+
+impl Validate for UpdateFieldTag {
+    fn validate(&self) -> Result<(), SorjordetError> {
+        self.name.reject_null("name")?;
+        if let Some(name) = self.name.value() {
+            validate_name("name", name, 128)?;
+        }
+        if let Some(category) = self.category.value() {
+            validate_name("category", category, 128)?;
+        }
+        if let Some(color) = self.draw_color.value() {
+            validate_color("draw_color", color, 64)?;
+        }
+        Ok(())
+    }
+}
+
+/// The active fields a tag should be on, it is removed from active fields not listed.
+#[derive(Deserialize, TS)]
+#[ts(export)]
+pub struct SetFieldTagFields {
+    pub fields: Vec<i32>,
+}
+
+#[derive(Deserialize)]
+struct FieldTagParams {
+    farm_id: Option<i32>,
+    category: Option<String>,
+}
+
+async fn get_tags(
+    State(pool): State<PgPool>,
+    extract::Query(params): extract::Query<FieldTagParams>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    let result: Vec<FieldTag> = query_as!(
+        FieldTag,
+        r#"SELECT t.id, t.farm_id, t.name, t.category, t.draw_color,
+                    ARRAY(
+                        SELECT ft.field_id
+                        FROM farm_field_tag ft
+                            JOIN farm_field f ON f.id = ft.field_id
+                        WHERE ft.tag_id = t.id AND f.retired_at IS NULL
+                        ORDER BY ft.field_id
+                    ) as "fields!",
+                    t.version, t.created_at, t.created_by, t.updated_at, t.updated_by
+                FROM field_tag t
+                WHERE ($1::int IS NULL OR t.farm_id = $1)
+                    AND ($2::text IS NULL OR LOWER(t.category) = LOWER($2))
+                ORDER BY t.category NULLS FIRST, t.name
+            "#,
+        params.farm_id,
+        params.category
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(result))
+}
+
+async fn post_tag(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    ValidatedJson(payload): ValidatedJson<CreateFieldTag>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    let result = query_scalar!(
+        "INSERT INTO field_tag (farm_id, name, category, draw_color, created_by, updated_by)
+                VALUES ($1, $2, $3, $4, $5, $5)
+                RETURNING id
+            ",
+        payload.farm_id,
+        payload.name.trim(),
+        payload.category.as_deref().map(str::trim),
+        payload.draw_color,
+        &claims.sub
+    )
+    .fetch_one(&pool)
+    .await
+    .map_err(insert_error("field tag", payload.farm_id))?;
+
+    tracing::info!("new field_tag inserted by {}", claims.sub);
+
+    Ok(Json(result))
+}
+
+async fn patch_tag(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    extract::Path(tag_id): extract::Path<i32>,
+    IfMatch(expected_version): IfMatch,
+    ValidatedJson(payload): ValidatedJson<UpdateFieldTag>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    let result = query_scalar!(
+        "UPDATE field_tag
+                SET name = COALESCE($1, name),
+                    category = CASE WHEN $2 THEN $3 ELSE category END,
+                    draw_color = CASE WHEN $4 THEN $5 ELSE draw_color END,
+                    version = version + 1,
+                    updated_at = NOW(),
+                    updated_by = $8
+                WHERE id = $6 AND ($7::int IS NULL OR version = $7)
+                RETURNING version
+            ",
+        payload.name.value().map(|x| x.trim()),
+        payload.category.is_set(),
+        payload.category.value().map(|x| x.trim()),
+        payload.draw_color.is_set(),
+        payload.draw_color.value(),
+        tag_id,
+        expected_version,
+        &claims.sub
+    )
+    .fetch_optional(&pool)
+    .await
+    .map_err(name_conflict("field tag"))?;
+
+    let Some(version) = result else {
+        return Err(not_found_or_conflict(
+            current_version(&pool, tag_id).await?,
+            format!("field_tag with id {} not found", tag_id),
+        ));
+    };
+
+    tracing::info!("field_tag {} updated by {}", tag_id, claims.sub);
+
+    Ok(etag(version))
+}
+
+async fn delete_tag(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    extract::Path(tag_id): extract::Path<i32>,
+    IfMatch(expected_version): IfMatch,
+) -> Result<impl IntoResponse, SorjordetError> {
+    let mut tx = pool.begin().await?;
+
+    // the fields lose the tag
+    query!(
+        "UPDATE farm_field
+                SET version = version + 1,
+                    updated_at = NOW(),
+                    updated_by = $2
+                WHERE id IN (SELECT field_id FROM farm_field_tag WHERE tag_id = $1)
+            ",
+        tag_id,
+        &claims.sub
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let result = query!(
+        "DELETE FROM field_tag WHERE id = $1 AND ($2::int IS NULL OR version = $2)",
+        tag_id,
+        expected_version
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(not_found_or_conflict(
+            current_version(&pool, tag_id).await?,
+            format!("field_tag with id {} not found", tag_id),
+        ));
+    }
+
+    tx.commit().await?;
+
+    tracing::info!("field_tag {} deleted by {}", tag_id, claims.sub);
+
+    Ok(())
+}
+
+async fn put_tag_fields(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    extract::Path(tag_id): extract::Path<i32>,
+    IfMatch(expected_version): IfMatch,
+    Json(payload): Json<SetFieldTagFields>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    let mut fields = payload.fields;
+    fields.sort_unstable();
+    fields.dedup();
+
+    let mut tx = pool.begin().await?;
+
+    let Some(tag) = query!(
+        "SELECT farm_id, version FROM field_tag WHERE id = $1 FOR UPDATE",
+        tag_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    else {
+        return Err(SorjordetError::NotFound(format!(
+            "field_tag with id {} not found",
+            tag_id
+        )));
+    };
+    if expected_version.is_some_and(|x| x != tag.version) {
+        return Err(not_found_or_conflict(
+            Some(tag.version),
+            format!("field_tag with id {} not found", tag_id),
+        ));
+    }
+
+    let found = query!(
+        "SELECT id, farm_id, retired_at FROM farm_field WHERE id = ANY($1)",
+        &fields
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    if let Some(missing) = fields.iter().find(|id| !found.iter().any(|x| x.id == **id)) {
+        return Err(SorjordetError::InvalidInput(format!(
+            "field {missing} not found"
+        )));
+    }
+    if let Some(field) = found.iter().find(|x| x.farm_id != tag.farm_id) {
+        return Err(SorjordetError::InvalidInput(format!(
+            "field {} belongs to another farm than the tag",
+            field.id
+        )));
+    }
+    if let Some(field) = found.iter().find(|x| x.retired_at.is_some()) {
+        return Err(SorjordetError::InvalidInput(format!(
+            "field {} is retired",
+            field.id
+        )));
+    }
+
+    // retired fields keep their tags, they aren't listed so can't be sent back
+    let removed = query_scalar!(
+        "DELETE FROM farm_field_tag
+                WHERE tag_id = $1 AND NOT field_id = ANY($2)
+                    AND field_id IN (SELECT id FROM farm_field WHERE retired_at IS NULL)
+                RETURNING field_id
+            ",
+        tag_id,
+        &fields
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let added = query_scalar!(
+        "INSERT INTO farm_field_tag (field_id, tag_id)
+                SELECT UNNEST($2::int[]), $1
+                ON CONFLICT DO NOTHING
+                RETURNING field_id
+            ",
+        tag_id,
+        &fields
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let changed: Vec<i32> = removed.into_iter().chain(added).collect();
+    query!(
+        "UPDATE farm_field
+                SET version = version + 1,
+                    updated_at = NOW(),
+                    updated_by = $2
+                WHERE id = ANY($1)
+            ",
+        &changed,
+        &claims.sub
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let version = query_scalar!(
+        "UPDATE field_tag
+                SET version = version + 1,
+                    updated_at = NOW(),
+                    updated_by = $2
+                WHERE id = $1
+                RETURNING version
+            ",
+        tag_id,
+        &claims.sub
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    tracing::info!("fields of field_tag {} set by {}", tag_id, claims.sub);
+
+    Ok((etag(version), Json(fields)))
+}
+
+async fn current_version(pool: &PgPool, tag_id: i32) -> Result<Option<i32>, SorjordetError> {
+    let version = query_scalar!("SELECT version FROM field_tag WHERE id = $1", tag_id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(version)
+}
+
+pub fn field_tag_router() -> Router<PgPool> {
+    Router::new()
+        .route("/{tag_id}/fields", put(put_tag_fields))
+        .route("/{tag_id}", patch(patch_tag).delete(delete_tag))
+        .route("/", get(get_tags).post(post_tag))
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use axum::{
     self, Json, Router,
@@ -6,7 +6,7 @@ use axum::{
     response::IntoResponse,
     routing::{get, post},
 };
-use chrono::{DateTime, Datelike, Days, Utc};
+use chrono::{DateTime, Days, NaiveDate, NaiveTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::{FromRow, PgPool, query, query_as, query_scalar};
 use ts_rs::TS;
@@ -14,75 +14,252 @@ use ts_rs::TS;
 use crate::auth::Claims;
 use crate::errors::SorjordetError;
 
+use super::etag::{IfMatch, etag, not_found_or_conflict};
+use super::farm::resolve_season_start;
+use super::farm_field::ensure_active_field;
+use super::patch::Patch;
+use super::period::{Granularity, SeasonStart};
+use super::validation::{Validate, ValidatedJson};
+
 #[derive(Serialize, Deserialize, FromRow, TS)]
 #[ts(export)]
 pub struct HarvestEvent {
     pub id: i32,
-    pub value: i32,
+    pub value: f64,
     pub time: DateTime<Utc>,
     pub field_id: i32,
     pub type_name: String,
     pub type_id: i32,
+    pub version: i32,
+    pub created_at: DateTime<Utc>,
+    pub created_by: Option<String>,
+    pub updated_at: DateTime<Utc>,
+    pub updated_by: Option<String>,
 }
 
-#[derive(Serialize, TS)]
+#[derive(Deserialize, TS)]
 #[ts(export)]
-pub struct HarvestTimeseries {
-    date: String,
-    total: i64,
+pub struct CreateHarvestEvent {
+    pub value: f64,
+    pub time: DateTime<Utc>,
+    pub field_id: i32,
+    pub type_id: i32,
+}
+
+impl Validate for CreateHarvestEvent {
+    fn validate(&self) -> Result<(), SorjordetError> {
+        validate_harvest_value(self.value)
+    }
+}
+
+/// JSON merge patch for a harvest event, only the given members are changed.
+#[derive(Deserialize, TS)]
+#[ts(export)]
+pub struct UpdateHarvestEvent {
+    #[serde(default)]
+    #[ts(as = "Option<f64>", optional)]
+    pub value: Patch<f64>,
+    #[serde(default)]
+    #[ts(as = "Option<DateTime<Utc>>", optional)]
+    pub time: Patch<DateTime<Utc>>,
+    #[serde(default)]
+    #[ts(as = "Option<i32>", optional)]
+    pub type_id: Patch<i32>,
 }
 
-impl From<&HarvestTimeseriesRaw> for HarvestTimeseries {
-    fn from(row: &HarvestTimeseriesRaw) -> Self {
-        HarvestTimeseries {
-            total: row.value.unwrap_or(0),
-            date: format!("{}-{}", row.time_month.year(), row.time_month.month()),
+impl Validate for UpdateHarvestEvent {
+    fn validate(&self) -> Result<(), SorjordetError> {
+        self.value.reject_null("value")?;
+        self.time.reject_null("time")?;
+        self.type_id.reject_null("type_id")?;
+        if let Some(value) = self.value.value() {
+            validate_harvest_value(*value)?;
         }
+        Ok(())
     }
 }
 
+/// Harvests on `field_id` can only use harvest types of the field's farm that are not archived.
+pub async fn ensure_active_type(
+    pool: &PgPool,
+    type_id: i32,
+    field_id: i32,
+) -> Result<(), SorjordetError> {
+    let found = query!(
+        r#"SELECT t.archived, t.farm_id IS NOT DISTINCT FROM f.farm_id as "same_farm!"
+                FROM harvest_type t
+                    LEFT JOIN farm_field f ON f.id = $2
+                WHERE t.id = $1
+            "#,
+        type_id,
+        field_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    match found {
+        None => Err(SorjordetError::InvalidInput(format!(
+            "harvest_type with id {type_id} not found"
+        ))),
+        Some(x) if !x.same_farm => Err(SorjordetError::InvalidInput(format!(
+            "harvest_type with id {type_id} belongs to another farm than field {field_id}"
+        ))),
+        Some(x) if x.archived => Err(SorjordetError::InvalidInput(format!(
+            "harvest_type with id {type_id} is archived"
+        ))),
+        Some(_) => Ok(()),
+    }
+}
+
+fn validate_harvest_value(value: f64) -> Result<(), SorjordetError> {
+    if !value.is_finite() || value < 0.0 {
+        return Err(SorjordetError::InvalidInput(
+            "value must be a number, not negative".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Serialize, TS)]
+#[ts(export)]
+pub struct HarvestTimeseries {
+    /// ISO formatted key of the period, see `Granularity::key`.
+    date: String,
+    total: f64,
+}
+
+/// Harvest totals per period for one harvest type,
+/// and for one field, group or farm unless grouping by harvest type.
 #[derive(Serialize, TS)]
 #[ts(export)]
 pub struct HarvestAggregated {
     type_id: i32,
     type_name: String,
+    /// Id of the field, group, tag or farm, `None` when grouping by harvest type or for ungrouped fields.
+    key_id: Option<i32>,
+    key_name: Option<String>,
+    /// Unit of the totals, the harvest type's own unit unless another was requested.
+    unit: String,
     harvests: Vec<HarvestTimeseries>,
 }
 
-impl From<HarvestTimeseriesRaw> for HarvestAggregated {
-    fn from(row: HarvestTimeseriesRaw) -> Self {
-        HarvestAggregated {
-            harvests: vec![HarvestTimeseries::from(&row)],
-            type_id: row.id,
-            type_name: row.name,
-        }
-    }
-}
-
 #[derive(Deserialize, FromRow, Serialize, TS)]
 #[ts(export)]
 pub struct GroupHarvestAgg {
     group_id: i32,
     group_name: String,
     group_color: String,
-    value: i64,
+    /// Unit of the total, one row per unit when summing in the harvest types' own units.
+    unit: String,
+    value: f64,
+}
+
+#[derive(Deserialize, FromRow, Serialize, TS)]
+#[ts(export)]
+pub struct TagHarvestAgg {
+    tag_id: i32,
+    tag_name: String,
+    tag_category: Option<String>,
+    tag_color: Option<String>,
+    /// Unit of the total, one row per unit when summing in the harvest types' own units.
+    unit: String,
+    value: f64,
 }
 
-#[derive(Deserialize, FromRow)]
+#[derive(Deserialize, FromRow, Clone)]
 struct HarvestTimeseriesRaw {
-    id: i32,
-    name: String,
-    value: Option<i64>,
-    time_month: DateTime<Utc>,
+    type_id: i32,
+    type_name: String,
+    key_id: Option<i32>,
+    key_name: Option<String>,
+    unit: String,
+    day: NaiveDate,
+    value: f64,
+}
+
+#[derive(Deserialize, Serialize, TS, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum HarvestGroupBy {
+    #[default]
+    HarvestType,
+    Field,
+    Group,
+    /// A field with several tags counts under each of them, untagged fields under none.
+    Tag,
+    Farm,
+}
+
+impl HarvestGroupBy {
+    fn as_str(&self) -> &'static str {
+        match self {
+            HarvestGroupBy::HarvestType => "harvest_type",
+            HarvestGroupBy::Field => "field",
+            HarvestGroupBy::Group => "group",
+            HarvestGroupBy::Tag => "tag",
+            HarvestGroupBy::Farm => "farm",
+        }
+    }
+}
+
+/// Unit harvest statistics are reported in.
+#[derive(Deserialize, Serialize, TS, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum ReportUnit {
+    /// Each harvest type's own unit.
+    #[default]
+    Native,
+    Kg,
+    Tonne,
+}
+
+impl ReportUnit {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ReportUnit::Native => "native",
+            ReportUnit::Kg => "kg",
+            ReportUnit::Tonne => "tonne",
+        }
+    }
+}
+
+/// Unit conversion of harvest statistics. Harvest types missing the
+/// bale weight or dry matter percentage needed are left out.
+#[derive(Clone, Copy)]
+struct UnitParams {
+    unit: ReportUnit,
+    /// Report the dry matter content instead of the harvested amount.
+    dry_matter: bool,
 }
 
 #[derive(Deserialize, Default)]
 pub struct HarvestAggParams {
     from: Option<DateTime<Utc>>,
     to: Option<DateTime<Utc>>,
+    #[serde(default)]
+    granularity: Granularity,
+    #[serde(default)]
+    group_by: HarvestGroupBy,
+    /// Only include harvests from this farm, whose season is used unless `season_start` is given.
+    farm_id: Option<i32>,
+    /// `MM-DD` start of the farming season, used with `granularity=season`.
+    season_start: Option<SeasonStart>,
+    #[serde(default)]
+    unit: ReportUnit,
+    /// Report the dry matter content instead of the harvested amount, see `UnitParams`.
+    #[serde(default)]
+    dry_matter: bool,
 }
 
 impl HarvestAggParams {
+    fn units(&self) -> UnitParams {
+        UnitParams {
+            unit: self.unit,
+            dry_matter: self.dry_matter,
+        }
+    }
+
     fn get_from_to(&self) -> (DateTime<Utc>, DateTime<Utc>) {
         let to = self
             .to
@@ -92,40 +269,115 @@ impl HarvestAggParams {
     }
 }
 
+/// Harvest totals per day and harvest type, split by the field, group, tag or farm given by `group_by`.
+async fn daily_totals(
+    pool: &PgPool,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    group_by: HarvestGroupBy,
+    farm_id: Option<i32>,
+    units: UnitParams,
+) -> Result<Vec<HarvestTimeseriesRaw>, SorjordetError> {
+    let timeseries: Vec<HarvestTimeseriesRaw> = query_as!(
+        HarvestTimeseriesRaw,
+        r#"
+        SELECT t.id as type_id, t.name as type_name,
+            CASE $3 WHEN 'field' THEN f.id WHEN 'group' THEN g.id WHEN 'tag' THEN tg.id WHEN 'farm' THEN fa.id END as key_id,
+            CASE $3 WHEN 'field' THEN f.name WHEN 'group' THEN g.name WHEN 'tag' THEN tg.name WHEN 'farm' THEN fa.name END as key_name,
+            CASE $5 WHEN 'native' THEN t.unit ELSE $5 END as "unit!",
+            (time AT TIME ZONE 'UTC')::date as "day!",
+            SUM(value * harvest_unit_factor(t.unit, t.kg_per_unit, t.dry_matter_pct, $5, $6)) as "value!"
+        FROM harvest_event e
+            JOIN harvest_type t ON t.id = e.harvest_type_id
+            JOIN farm_field f ON f.id = e.field_id
+            LEFT JOIN farm_field_group g ON g.id = f.farm_field_group_id
+            JOIN farm fa ON fa.id = f.farm_id
+            -- one row per tag when grouping by tag, otherwise once per harvest
+            CROSS JOIN LATERAL (
+                SELECT tg.id, tg.name
+                FROM farm_field_tag ft JOIN field_tag tg ON tg.id = ft.tag_id
+                WHERE $3 = 'tag' AND ft.field_id = f.id
+                UNION ALL
+                SELECT NULL, NULL WHERE $3 <> 'tag'
+            ) tg
+        WHERE time BETWEEN $1 AND $2
+            AND ($4::int IS NULL OR f.farm_id = $4)
+            AND harvest_unit_factor(t.unit, t.kg_per_unit, t.dry_matter_pct, $5, $6) IS NOT NULL
+        GROUP BY 1, 2, 3, 4, 5, 6
+        ORDER BY 6
+    "#,
+        from,
+        to,
+        group_by.as_str(),
+        farm_id,
+        units.unit.as_str(),
+        units.dry_matter
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(timeseries)
+}
+
 async fn get_aggregated_harvests(
     State(pool): State<PgPool>,
     harvest_params: Query<HarvestAggParams>,
 ) -> Result<impl IntoResponse, SorjordetError> {
     let (from, to) = harvest_params.get_from_to();
+    let granularity = harvest_params.granularity;
+    let season_start =
+        resolve_season_start(&pool, harvest_params.season_start, harvest_params.farm_id).await?;
 
-    let timeseries: Vec<HarvestTimeseriesRaw> = query_as!(
-        HarvestTimeseriesRaw,
-        r#"
-        SELECT t.id, t.name, SUM(value) as value, DATE_TRUNC('month', time) as "time_month!" FROM
-        harvest_event e JOIN harvest_type t ON  t.id=e.harvest_type_id
-        WHERE time BETWEEN $1 AND $2
-        GROUP BY t.id, 4
-        ORDER BY 4
-    "#,
+    let periods = granularity.periods(from.date_naive(), to.date_naive(), season_start)?;
+
+    // summed per day, and bucketed into periods here since seasons don't map to DATE_TRUNC
+    let timeseries = daily_totals(
+        &pool,
         from,
-        to
+        to,
+        harvest_params.group_by,
+        harvest_params.farm_id,
+        harvest_params.units(),
     )
-    .fetch_all(&pool)
     .await?;
 
-    let results: Vec<HarvestAggregated> = timeseries
-        .into_iter()
-        .fold(HashMap::<i32, HarvestAggregated>::new(), |mut m, row| {
-            if let Some(x) = m.get_mut(&row.id) {
-                x.harvests.push(HarvestTimeseries::from(&row));
-            } else {
-                m.insert(row.id, HarvestAggregated::from(row));
-            }
-            m
-        })
+    let mut series =
+        BTreeMap::<(i32, Option<i32>), (HarvestTimeseriesRaw, BTreeMap<NaiveDate, f64>)>::new();
+    for row in timeseries {
+        let period = granularity.period_start(row.day, season_start);
+        let (_, totals) = series
+            .entry((row.type_id, row.key_id))
+            .or_insert_with(|| (row.clone(), BTreeMap::new()));
+        *totals.entry(period).or_default() += row.value;
+    }
+
+    let mut results: Vec<HarvestAggregated> = series
         .into_values()
+        .map(|(row, totals)| HarvestAggregated {
+            type_id: row.type_id,
+            type_name: row.type_name,
+            key_id: row.key_id,
+            key_name: row.key_name,
+            unit: row.unit,
+            harvests: periods
+                .iter()
+                .map(|start| HarvestTimeseries {
+                    date: granularity.key(*start, season_start),
+                    total: totals.get(start).copied().unwrap_or(0.0),
+                })
+                .collect(),
+        })
         .collect();
 
+    results.sort_by(|a, b| {
+        (&a.type_name, a.type_id, &a.key_name, a.key_id).cmp(&(
+            &b.type_name,
+            b.type_id,
+            &b.key_name,
+            b.key_id,
+        ))
+    });
+
     Ok(Json(results))
 }
 
@@ -138,15 +390,24 @@ async fn get_agged_group_harvests(
     let timeseries: Vec<GroupHarvestAgg> = query_as!(
         GroupHarvestAgg,
         r#"
-        SELECT coalesce(SUM(value), 0) as "value!", g.id as group_id, g.name as group_name, g.draw_color as group_color FROM
+        SELECT coalesce(SUM(value * u.factor), 0) as "value!", g.id as group_id, g.name as group_name, g.draw_color as group_color,
+            CASE $3 WHEN 'native' THEN t.unit ELSE $3 END as "unit!"
+        FROM
         harvest_event e JOIN farm_field f ON  f.id = e.field_id
         JOIN farm_field_group g ON g.id = f.farm_field_group_id
+        JOIN harvest_type t ON t.id = e.harvest_type_id
+        CROSS JOIN LATERAL (
+            SELECT harvest_unit_factor(t.unit, t.kg_per_unit, t.dry_matter_pct, $3, $4) as factor
+        ) u
         WHERE time BETWEEN $1 AND $2
-        GROUP BY g.id
-        ORDER BY 4
+            AND u.factor IS NOT NULL
+        GROUP BY g.id, 5
+        ORDER BY 4, 5
     "#,
         from,
-        to
+        to,
+        harvest_params.unit.as_str(),
+        harvest_params.dry_matter
     )
     .fetch_all(&pool)
     .await?;
@@ -154,13 +415,461 @@ async fn get_agged_group_harvests(
     Ok(Json(timeseries))
 }
 
+/// Harvest totals per tag, a field with several tags counts under each of them.
+async fn get_agged_tag_harvests(
+    State(pool): State<PgPool>,
+    harvest_params: Query<HarvestAggParams>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    let (from, to) = harvest_params.get_from_to();
+
+    let totals: Vec<TagHarvestAgg> = query_as!(
+        TagHarvestAgg,
+        r#"
+        SELECT coalesce(SUM(value * u.factor), 0) as "value!", tg.id as tag_id, tg.name as tag_name,
+            tg.category as tag_category, tg.draw_color as tag_color,
+            CASE $3 WHEN 'native' THEN t.unit ELSE $3 END as "unit!"
+        FROM harvest_event e
+            JOIN farm_field_tag ft ON ft.field_id = e.field_id
+            JOIN field_tag tg ON tg.id = ft.tag_id
+            JOIN harvest_type t ON t.id = e.harvest_type_id
+            CROSS JOIN LATERAL (
+                SELECT harvest_unit_factor(t.unit, t.kg_per_unit, t.dry_matter_pct, $3, $4) as factor
+            ) u
+        WHERE time BETWEEN $1 AND $2
+            AND ($5::int IS NULL OR tg.farm_id = $5)
+            AND u.factor IS NOT NULL
+        GROUP BY tg.id, 6
+        ORDER BY tg.category NULLS FIRST, tg.name, 6
+    "#,
+        from,
+        to,
+        harvest_params.unit.as_str(),
+        harvest_params.dry_matter,
+        harvest_params.farm_id
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(totals))
+}
+
+#[derive(Deserialize)]
+pub struct NutrientBalanceParams {
+    /// Farming season, using the season start of each field's farm.
+    season: Option<i32>,
+    farm_id: Option<i32>,
+    field_id: Option<i32>,
+}
+
+#[derive(Serialize, TS)]
+#[ts(export)]
+pub struct NutrientAmounts {
+    n_kg: f64,
+    p_kg: f64,
+    k_kg: f64,
+}
+
+/// Nutrients applied to a field in a season against those removed by its harvests.
+#[derive(Serialize, TS)]
+#[ts(export)]
+pub struct NutrientBalance {
+    field_id: i32,
+    field_name: String,
+    season: i32,
+    season_label: String,
+    applied: NutrientAmounts,
+    removed: NutrientAmounts,
+    /// Applied minus removed, negative when the field is being depleted.
+    balance: NutrientAmounts,
+    /// Harvest types in the season missing a removal coefficient, their
+    /// harvests don't count towards the removed amount of that nutrient.
+    missing_removal_types: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct FieldYieldParams {
+    /// Farming season, using the season start of each field's farm.
+    season: Option<i32>,
+    farm_id: Option<i32>,
+    field_id: Option<i32>,
+    /// With `field_id`, also include the fields it was split or merged from.
+    #[serde(default)]
+    include_predecessors: bool,
+    #[serde(default)]
+    unit: ReportUnit,
+    /// Report the dry matter content instead of the harvested amount, see `UnitParams`.
+    #[serde(default)]
+    dry_matter: bool,
+}
+
+/// Harvest of one type from a field in a season, relative to the field's area.
+#[derive(Serialize, TS)]
+#[ts(export)]
+pub struct FieldYield {
+    field_id: i32,
+    field_name: String,
+    type_id: i32,
+    type_name: String,
+    season: i32,
+    season_label: String,
+    unit: String,
+    total: f64,
+    /// Area of the field at the season's last harvest, in hectares.
+    area_ha: Option<f64>,
+    /// Each harvest divided by the area of the boundary valid when it was made.
+    /// Harvests from before the first recorded boundary use that boundary,
+    /// fields without any boundary have no yield per hectare.
+    yield_per_ha: Option<f64>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct SeasonComparisonParams {
+    /// Number of seasons to compare, including the current one.
+    seasons: Option<u32>,
+    /// Compare up to this day of the season instead of today.
+    at: Option<DateTime<Utc>>,
+    /// Only include harvests from this farm, whose season is used unless `season_start` is given.
+    farm_id: Option<i32>,
+    /// `MM-DD` start of the farming season.
+    season_start: Option<SeasonStart>,
+    #[serde(default)]
+    unit: ReportUnit,
+    /// Report the dry matter content instead of the harvested amount, see `UnitParams`.
+    #[serde(default)]
+    dry_matter: bool,
+}
+
+const DEFAULT_COMPARED_SEASONS: u32 = 5;
+const MAX_COMPARED_SEASONS: u32 = 30;
+
+#[derive(Serialize, TS)]
+#[ts(export)]
+pub struct SeasonDayTotal {
+    /// Days since the start of the season, the first day being 0.
+    day: i64,
+    total: f64,
+}
+
+#[derive(Serialize, TS)]
+#[ts(export)]
+pub struct SeasonCumulative {
+    season: i32,
+    label: String,
+    /// Cumulative total up to and including the compared day of season.
+    total_to_date: f64,
+    /// Total for the whole season.
+    total: f64,
+    /// Cumulative total on each day of the season with harvests.
+    cumulative: Vec<SeasonDayTotal>,
+}
+
+#[derive(Serialize, TS)]
+#[ts(export)]
+pub struct HarvestSeasonComparison {
+    type_id: i32,
+    type_name: String,
+    /// Id of the field or group, `None` when comparing per harvest type or for ungrouped fields.
+    key_id: Option<i32>,
+    key_name: Option<String>,
+    unit: String,
+    /// Compared seasons, most recent first.
+    seasons: Vec<SeasonCumulative>,
+}
+
+#[derive(Serialize, TS)]
+#[ts(export)]
+pub struct SeasonComparison {
+    current_season: i32,
+    current_label: String,
+    /// The day of season every season is compared at.
+    day_of_season: i64,
+    by_type: Vec<HarvestSeasonComparison>,
+    by_group: Vec<HarvestSeasonComparison>,
+    by_field: Vec<HarvestSeasonComparison>,
+}
+
+/// Cumulative harvest per season for each series in `timeseries`, aligned by day of season.
+fn compare_seasons(
+    timeseries: Vec<HarvestTimeseriesRaw>,
+    seasons: &[i32],
+    season_start: SeasonStart,
+    day_of_season: i64,
+) -> Vec<HarvestSeasonComparison> {
+    // rows are ordered by day, so totals can be accumulated in order
+    let mut series = BTreeMap::<
+        (i32, Option<i32>),
+        (HarvestTimeseriesRaw, BTreeMap<i32, Vec<SeasonDayTotal>>),
+    >::new();
+    for row in timeseries {
+        let season = season_start.season_of(row.day);
+        let day = (row.day - season_start.start_of(season)).num_days();
+        let (_, by_season) = series
+            .entry((row.type_id, row.key_id))
+            .or_insert_with(|| (row.clone(), BTreeMap::new()));
+        let cumulative = by_season.entry(season).or_default();
+        let total = cumulative.last().map(|x| x.total).unwrap_or(0.0) + row.value;
+        cumulative.push(SeasonDayTotal { day, total });
+    }
+
+    let mut results: Vec<HarvestSeasonComparison> = series
+        .into_values()
+        .map(|(row, mut by_season)| HarvestSeasonComparison {
+            type_id: row.type_id,
+            type_name: row.type_name,
+            key_id: row.key_id,
+            key_name: row.key_name,
+            unit: row.unit,
+            seasons: seasons
+                .iter()
+                .map(|season| {
+                    let cumulative = by_season.remove(season).unwrap_or_default();
+                    SeasonCumulative {
+                        season: *season,
+                        label: season_start.label(*season),
+                        total_to_date: cumulative
+                            .iter()
+                            .take_while(|x| x.day <= day_of_season)
+                            .last()
+                            .map(|x| x.total)
+                            .unwrap_or(0.0),
+                        total: cumulative.last().map(|x| x.total).unwrap_or(0.0),
+                        cumulative,
+                    }
+                })
+                .collect(),
+        })
+        .collect();
+
+    results.sort_by(|a, b| {
+        (&a.type_name, a.type_id, &a.key_name, a.key_id).cmp(&(
+            &b.type_name,
+            b.type_id,
+            &b.key_name,
+            b.key_id,
+        ))
+    });
+
+    results
+}
+
+async fn get_nutrient_balance(
+    State(pool): State<PgPool>,
+    Query(params): Query<NutrientBalanceParams>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    let rows = query!(
+        r#"
+        WITH applied AS (
+            SELECT e.field_id,
+                farm_season(e.time, fa.season_start_month, fa.season_start_day) as season,
+                COALESCE(SUM(a.amount * a.n_kg_per_unit), 0) as n_kg,
+                COALESCE(SUM(a.amount * a.p_kg_per_unit), 0) as p_kg,
+                COALESCE(SUM(a.amount * a.k_kg_per_unit), 0) as k_kg
+            FROM field_application a
+                JOIN field_event e ON e.id = a.field_event_id
+                JOIN farm_field f ON f.id = e.field_id
+                JOIN farm fa ON fa.id = f.farm_id
+            GROUP BY 1, 2
+        ),
+        removed AS (
+            SELECT h.field_id,
+                farm_season(h.time, fa.season_start_month, fa.season_start_day) as season,
+                COALESCE(SUM(h.value * t.n_removal_kg_per_unit), 0) as n_kg,
+                COALESCE(SUM(h.value * t.p_removal_kg_per_unit), 0) as p_kg,
+                COALESCE(SUM(h.value * t.k_removal_kg_per_unit), 0) as k_kg,
+                ARRAY_AGG(DISTINCT t.name) FILTER (
+                    WHERE t.n_removal_kg_per_unit IS NULL
+                        OR t.p_removal_kg_per_unit IS NULL
+                        OR t.k_removal_kg_per_unit IS NULL
+                ) as missing_types
+            FROM harvest_event h
+                JOIN harvest_type t ON t.id = h.harvest_type_id
+                JOIN farm_field f ON f.id = h.field_id
+                JOIN farm fa ON fa.id = f.farm_id
+            GROUP BY 1, 2
+        )
+        SELECT f.id as field_id, f.name as field_name,
+            COALESCE(a.season, r.season) as "season!",
+            fa.season_start_month, fa.season_start_day,
+            COALESCE(a.n_kg, 0) as "applied_n_kg!",
+            COALESCE(a.p_kg, 0) as "applied_p_kg!",
+            COALESCE(a.k_kg, 0) as "applied_k_kg!",
+            COALESCE(r.n_kg, 0) as "removed_n_kg!",
+            COALESCE(r.p_kg, 0) as "removed_p_kg!",
+            COALESCE(r.k_kg, 0) as "removed_k_kg!",
+            r.missing_types
+        FROM applied a
+            FULL JOIN removed r ON r.field_id = a.field_id AND r.season = a.season
+            JOIN farm_field f ON f.id = COALESCE(a.field_id, r.field_id)
+            JOIN farm fa ON fa.id = f.farm_id
+        WHERE ($1::int IS NULL OR COALESCE(a.season, r.season) = $1)
+            AND ($2::int IS NULL OR f.farm_id = $2)
+            AND ($3::int IS NULL OR f.id = $3)
+        ORDER BY f.name, f.id, 3 DESC
+    "#,
+        params.season,
+        params.farm_id,
+        params.field_id
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    let balances: Vec<NutrientBalance> = rows
+        .into_iter()
+        .map(|x| {
+            let season_start =
+                SeasonStart::new(x.season_start_month as u32, x.season_start_day as u32)
+                    .unwrap_or_default();
+            NutrientBalance {
+                field_id: x.field_id,
+                field_name: x.field_name,
+                season: x.season,
+                season_label: season_start.label(x.season),
+                balance: NutrientAmounts {
+                    n_kg: x.applied_n_kg - x.removed_n_kg,
+                    p_kg: x.applied_p_kg - x.removed_p_kg,
+                    k_kg: x.applied_k_kg - x.removed_k_kg,
+                },
+                applied: NutrientAmounts {
+                    n_kg: x.applied_n_kg,
+                    p_kg: x.applied_p_kg,
+                    k_kg: x.applied_k_kg,
+                },
+                removed: NutrientAmounts {
+                    n_kg: x.removed_n_kg,
+                    p_kg: x.removed_p_kg,
+                    k_kg: x.removed_k_kg,
+                },
+                missing_removal_types: x.missing_types.unwrap_or_default(),
+            }
+        })
+        .collect();
+
+    Ok(Json(balances))
+}
+
+async fn get_field_yields(
+    State(pool): State<PgPool>,
+    Query(params): Query<FieldYieldParams>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    let rows = query!(
+        r#"
+        SELECT f.id as field_id, f.name as field_name, t.id as type_id, t.name as type_name,
+            farm_season(h.time, fa.season_start_month, fa.season_start_day) as "season!",
+            fa.season_start_month, fa.season_start_day,
+            CASE $4 WHEN 'native' THEN t.unit ELSE $4 END as "unit!",
+            SUM(h.value * u.factor) as "total!",
+            SUM(h.value * u.factor / NULLIF(a.area_m2, 0)) * 10000 as yield_per_ha,
+            (ARRAY_AGG(a.area_m2 ORDER BY h.time DESC))[1] / 10000 as area_ha
+        FROM harvest_event h
+            JOIN harvest_type t ON t.id = h.harvest_type_id
+            JOIN farm_field f ON f.id = h.field_id
+            JOIN farm fa ON fa.id = f.farm_id
+            CROSS JOIN LATERAL (
+                SELECT harvest_unit_factor(t.unit, t.kg_per_unit, t.dry_matter_pct, $4, $5) as factor
+            ) u
+            CROSS JOIN LATERAL (
+                SELECT ST_Area(field_boundary_at(f.id, h.time)::geography) as area_m2
+            ) a
+        WHERE u.factor IS NOT NULL
+            AND ($1::int IS NULL
+                OR farm_season(h.time, fa.season_start_month, fa.season_start_day) = $1)
+            AND ($2::int IS NULL OR f.farm_id = $2)
+            AND ($3::int IS NULL OR f.id = $3
+                OR ($6 AND f.id IN (SELECT field_id FROM field_with_predecessors($3))))
+        GROUP BY f.id, f.name, t.id, t.name, 5, fa.season_start_month, fa.season_start_day, 8
+        ORDER BY f.name, f.id, t.name, t.id, 5 DESC
+    "#,
+        params.season,
+        params.farm_id,
+        params.field_id,
+        params.unit.as_str(),
+        params.dry_matter,
+        params.include_predecessors
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    let yields: Vec<FieldYield> = rows
+        .into_iter()
+        .map(|x| {
+            let season_start =
+                SeasonStart::new(x.season_start_month as u32, x.season_start_day as u32)
+                    .unwrap_or_default();
+            FieldYield {
+                field_id: x.field_id,
+                field_name: x.field_name,
+                type_id: x.type_id,
+                type_name: x.type_name,
+                season: x.season,
+                season_label: season_start.label(x.season),
+                unit: x.unit,
+                total: x.total,
+                area_ha: x.area_ha,
+                yield_per_ha: x.yield_per_ha,
+            }
+        })
+        .collect();
+
+    Ok(Json(yields))
+}
+
+async fn get_season_comparison(
+    State(pool): State<PgPool>,
+    Query(params): Query<SeasonComparisonParams>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    let season_count = params.seasons.unwrap_or(DEFAULT_COMPARED_SEASONS);
+    if !(1..=MAX_COMPARED_SEASONS).contains(&season_count) {
+        return Err(SorjordetError::InvalidInput(format!(
+            "seasons must be between 1 and {MAX_COMPARED_SEASONS}"
+        )));
+    }
+    let season_start = resolve_season_start(&pool, params.season_start, params.farm_id).await?;
+    let at = params.at.unwrap_or(chrono::Utc::now()).date_naive();
+
+    let current_season = season_start.season_of(at);
+    let day_of_season = (at - season_start.start_of(current_season)).num_days();
+    let seasons: Vec<i32> = (0..season_count as i32)
+        .map(|x| current_season - x)
+        .collect();
+
+    let first_season = current_season - season_count as i32 + 1;
+    let from = season_start
+        .start_of(first_season)
+        .and_time(NaiveTime::MIN)
+        .and_utc();
+    let to = season_start
+        .start_of(current_season + 1)
+        .and_time(NaiveTime::MIN)
+        .and_utc();
+
+    let farm_id = params.farm_id;
+    let units = UnitParams {
+        unit: params.unit,
+        dry_matter: params.dry_matter,
+    };
+    let by_type =
+        daily_totals(&pool, from, to, HarvestGroupBy::HarvestType, farm_id, units).await?;
+    let by_group = daily_totals(&pool, from, to, HarvestGroupBy::Group, farm_id, units).await?;
+    let by_field = daily_totals(&pool, from, to, HarvestGroupBy::Field, farm_id, units).await?;
+
+    Ok(Json(SeasonComparison {
+        current_season,
+        current_label: season_start.label(current_season),
+        day_of_season,
+        by_type: compare_seasons(by_type, &seasons, season_start, day_of_season),
+        by_group: compare_seasons(by_group, &seasons, season_start, day_of_season),
+        by_field: compare_seasons(by_field, &seasons, season_start, day_of_season),
+    }))
+}
+
 async fn get_events(
     State(pool): State<PgPool>,
     extract::Path(field_id): extract::Path<i32>,
 ) -> Result<impl IntoResponse, SorjordetError> {
     let result: Vec<HarvestEvent> = query_as!(
         HarvestEvent,
-        "SELECT e.id, value, time, field_id, h.name as type_name, h.id as type_id
+        "SELECT e.id, value, time, field_id, h.name as type_name, h.id as type_id, e.version,
+                    e.created_at, e.created_by, e.updated_at, e.updated_by
                 FROM harvest_event AS e JOIN harvest_type AS h ON e.harvest_type_id = h.id
                 WHERE field_id = $1
                 ORDER BY time DESC
@@ -176,17 +885,22 @@ async fn get_events(
 async fn post_event(
     claims: Claims,
     State(pool): State<PgPool>,
-    extract::Json(payload): extract::Json<HarvestEvent>,
+    ValidatedJson(payload): ValidatedJson<CreateHarvestEvent>,
 ) -> Result<impl IntoResponse, SorjordetError> {
+    ensure_active_field(&pool, payload.field_id).await?;
+    ensure_active_type(&pool, payload.type_id, payload.field_id).await?;
+
     let result = query_scalar!(
-        "INSERT INTO harvest_event (value, time, field_id, harvest_type_id)
-                VALUES ($1,$2, $3, $4)
+        "INSERT INTO harvest_event
+                    (value, time, field_id, harvest_type_id, created_by, updated_by)
+                VALUES ($1,$2, $3, $4, $5, $5)
                 RETURNING id
             ",
         &payload.value,
         &payload.time,
         &payload.field_id,
-        &payload.type_id
+        &payload.type_id,
+        &claims.sub
     )
     .fetch_one(&pool)
     .await?;
@@ -200,55 +914,149 @@ async fn patch_event(
     claims: Claims,
     State(pool): State<PgPool>,
     extract::Path(event_id): extract::Path<i32>,
-    extract::Json(payload): extract::Json<HarvestEvent>,
+    IfMatch(expected_version): IfMatch,
+    ValidatedJson(payload): ValidatedJson<UpdateHarvestEvent>,
 ) -> Result<impl IntoResponse, SorjordetError> {
-    let result = query!(
-        "UPDATE harvest_event
-                SET value = $1, time = $2, harvest_type_id = $3
-                WHERE id = $4
-            ",
-        &payload.value,
-        &payload.time,
-        &payload.type_id,
-        &event_id
+    if let Some(type_id) = payload.type_id.value() {
+        let field_id = query_scalar!("SELECT field_id FROM harvest_event WHERE id = $1", event_id)
+            .fetch_optional(&pool)
+            .await?
+            .ok_or_else(|| {
+                SorjordetError::NotFound(format!("harvest_event with id {} not found", event_id))
+            })?;
+        ensure_active_type(&pool, *type_id, field_id).await?;
+    }
+
+    let result: Option<HarvestEvent> = query_as!(
+        HarvestEvent,
+        r#"WITH e AS (
+                UPDATE harvest_event
+                SET value = COALESCE($1, value),
+                    time = COALESCE($2, time),
+                    harvest_type_id = COALESCE($3, harvest_type_id),
+                    version = version + 1,
+                    updated_at = NOW(),
+                    updated_by = $6
+                WHERE id = $4 AND ($5::int IS NULL OR version = $5)
+                RETURNING id, value, time, field_id, harvest_type_id, version,
+                    created_at, created_by, updated_at, updated_by
+            )
+            SELECT e.id, e.value, e.time, e.field_id, h.name as type_name, h.id as type_id, e.version,
+                    e.created_at, e.created_by, e.updated_at, e.updated_by
+                FROM e JOIN harvest_type AS h ON e.harvest_type_id = h.id
+            "#,
+        payload.value.value(),
+        payload.time.value(),
+        payload.type_id.value(),
+        &event_id,
+        expected_version,
+        &claims.sub
     )
-    .execute(&pool)
+    .fetch_optional(&pool)
     .await?;
 
-    if result.rows_affected() == 0 {
-        return Err(SorjordetError::NotFound(format!(
-            "harvest_event with id {} not found",
-            event_id
-        )));
-    }
+    let Some(event) = result else {
+        return Err(not_found_or_conflict(
+            current_version(&pool, event_id).await?,
+            format!("harvest_event with id {} not found", event_id),
+        ));
+    };
 
     tracing::info!("harvest_event {event_id} updated by {}", claims.sub);
 
-    Ok(Json(payload))
+    Ok((etag(event.version), Json(event)))
 }
 
 async fn delete_event(
     claims: Claims,
     State(pool): State<PgPool>,
     extract::Path(event_id): extract::Path<i32>,
+    IfMatch(expected_version): IfMatch,
 ) -> Result<impl IntoResponse, SorjordetError> {
-    query!("DELETE FROM harvest_event WHERE id = $1", event_id)
-        .execute(&pool)
-        .await?;
+    let result = query!(
+        "DELETE FROM harvest_event WHERE id = $1 AND ($2::int IS NULL OR version = $2)",
+        event_id,
+        expected_version
+    )
+    .execute(&pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(not_found_or_conflict(
+            current_version(&pool, event_id).await?,
+            format!("harvest_event with id {} not found", event_id),
+        ));
+    }
 
     tracing::info!("harvest_event {} deleted by {}", event_id, claims.sub);
 
     Ok(())
 }
 
+async fn current_version(pool: &PgPool, event_id: i32) -> Result<Option<i32>, SorjordetError> {
+    let version = query_scalar!("SELECT version FROM harvest_event WHERE id = $1", event_id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(version)
+}
+
+#[derive(Deserialize, Serialize, TS, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+enum HarvestSort {
+    #[default]
+    TimeDesc,
+    TimeAsc,
+    ValueDesc,
+    ValueAsc,
+}
+
+impl HarvestSort {
+    fn as_str(&self) -> &'static str {
+        match self {
+            HarvestSort::TimeDesc => "time_desc",
+            HarvestSort::TimeAsc => "time_asc",
+            HarvestSort::ValueDesc => "value_desc",
+            HarvestSort::ValueAsc => "value_asc",
+        }
+    }
+}
+
+const MAX_PAGE_SIZE: i32 = 500;
+
 #[derive(Deserialize, Serialize, TS)]
 #[ts(export)]
 struct HarvestParams {
-    year: i32,
+    year: Option<i32>,
+    /// Farming season, using the season start of each field's farm.
+    season: Option<i32>,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
     page: i32,
     page_size: i32,
     field_id: Option<i32>,
     group_id: Option<i32>,
+    /// Only harvests on fields with this tag.
+    tag_id: Option<i32>,
+    #[serde(default)]
+    sort: HarvestSort,
+}
+
+impl Validate for HarvestParams {
+    fn validate(&self) -> Result<(), SorjordetError> {
+        if self.page < 1 {
+            return Err(SorjordetError::InvalidInput(
+                "page must be 1 or greater".to_string(),
+            ));
+        }
+        if !(1..=MAX_PAGE_SIZE).contains(&self.page_size) {
+            return Err(SorjordetError::InvalidInput(format!(
+                "page_size must be between 1 and {MAX_PAGE_SIZE}"
+            )));
+        }
+        Ok(())
+    }
 }
 
 #[derive(Deserialize, Serialize, TS)]
@@ -256,38 +1064,90 @@ struct HarvestParams {
 struct HarvestPagination {
     params: HarvestParams,
     events: Vec<HarvestEvent>,
+    total_count: i64,
+    page_count: i64,
 }
 
 async fn paginated_events(
     State(pool): State<PgPool>,
     extract::Query(params): extract::Query<HarvestParams>,
 ) -> Result<impl IntoResponse, SorjordetError> {
-    let page_offset = (params.page - 1) * params.page_size;
+    params.validate()?;
+
+    let page_offset = (params.page as i64 - 1) * params.page_size as i64;
     let result: Vec<HarvestEvent> = query_as!(
         HarvestEvent,
-        "SELECT e.id, value, time, field_id, h.name as type_name, h.id as type_id
-                FROM harvest_event AS e 
+        "SELECT e.id, value, time, field_id, h.name as type_name, h.id as type_id, e.version,
+                    e.created_at, e.created_by, e.updated_at, e.updated_by
+                FROM harvest_event AS e
                     JOIN harvest_type AS h ON e.harvest_type_id = h.id
                     JOIN farm_field f ON f.id = e.field_id
-                    JOIN farm_field_group fg ON f.farm_field_group_id = fg.id
-                WHERE CAST(EXTRACT(year from time) as integer) = $1 
-                    AND ($4 = -1 OR f.id = $4)
-                    AND ($5 = -1 OR fg.id = $5)
-                ORDER BY time DESC
-                LIMIT $2 OFFSET $3
+                    JOIN farm fa ON fa.id = f.farm_id
+                WHERE ($1::int IS NULL OR CAST(EXTRACT(year from time) as integer) = $1)
+                    AND ($9::int IS NULL
+                        OR farm_season(time, fa.season_start_month, fa.season_start_day) = $9)
+                    AND ($2::timestamptz IS NULL OR time >= $2)
+                    AND ($3::timestamptz IS NULL OR time < $3)
+                    AND ($4::int IS NULL OR f.id = $4)
+                    AND ($5::int IS NULL OR f.farm_field_group_id = $5)
+                    AND ($10::int IS NULL OR EXISTS (
+                        SELECT 1 FROM farm_field_tag ft WHERE ft.field_id = f.id AND ft.tag_id = $10
+                    ))
+                ORDER BY
+                    CASE WHEN $6 = 'time_asc' THEN time END ASC,
+                    CASE WHEN $6 = 'value_desc' THEN value END DESC,
+                    CASE WHEN $6 = 'value_asc' THEN value END ASC,
+                    time DESC, e.id DESC
+                LIMIT $7 OFFSET $8
             ",
         params.year,
+        params.from,
+        params.to,
+        params.field_id,
+        params.group_id,
+        params.sort.as_str(),
         params.page_size as i64,
-        page_offset as i64,
-        params.field_id.unwrap_or(-1),
-        params.group_id.unwrap_or(-1)
+        page_offset,
+        params.season,
+        params.tag_id
     )
     .fetch_all(&pool)
     .await?;
 
+    let total_count = query_scalar!(
+        r#"SELECT COUNT(*) as "count!"
+                FROM harvest_event AS e
+                    JOIN farm_field f ON f.id = e.field_id
+                    JOIN farm fa ON fa.id = f.farm_id
+                WHERE ($1::int IS NULL OR CAST(EXTRACT(year from time) as integer) = $1)
+                    AND ($6::int IS NULL
+                        OR farm_season(time, fa.season_start_month, fa.season_start_day) = $6)
+                    AND ($2::timestamptz IS NULL OR time >= $2)
+                    AND ($3::timestamptz IS NULL OR time < $3)
+                    AND ($4::int IS NULL OR f.id = $4)
+                    AND ($5::int IS NULL OR f.farm_field_group_id = $5)
+                    AND ($7::int IS NULL OR EXISTS (
+                        SELECT 1 FROM farm_field_tag ft WHERE ft.field_id = f.id AND ft.tag_id = $7
+                    ))
+            "#,
+        params.year,
+        params.from,
+        params.to,
+        params.field_id,
+        params.group_id,
+        params.season,
+        params.tag_id
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    let page_count = (total_count + params.page_size as i64 - 1) / params.page_size as i64;
+
     let paginated = HarvestPagination {
         params,
         events: result,
+        total_count,
+        page_count,
     };
 
     Ok(Json(paginated))
@@ -296,7 +1156,11 @@ async fn paginated_events(
 pub fn harvest_event_router() -> Router<PgPool> {
     Router::new()
         .route("/aggregated_group_harvests", get(get_agged_group_harvests))
+        .route("/aggregated_tag_harvests", get(get_agged_tag_harvests))
         .route("/aggregated_harvests", get(get_aggregated_harvests))
+        .route("/nutrient_balance", get(get_nutrient_balance))
+        .route("/field_yields", get(get_field_yields))
+        .route("/season_comparison", get(get_season_comparison))
         .route(
             "/{id}",
             get(get_events).patch(patch_event).delete(delete_event),
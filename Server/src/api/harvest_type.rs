@@ -2,8 +2,9 @@ use axum::{
     self, Json, Router,
     extract::{self, State},
     response::IntoResponse,
-    routing::{get, patch},
+    routing::{get, patch, post},
 };
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::{FromRow, PgPool, query, query_as, query_scalar};
 use ts_rs::TS;
@@ -11,17 +12,197 @@ use ts_rs::TS;
 use crate::auth::Claims;
 use crate::errors::SorjordetError;
 
+use super::catalogue::{
+    check_merge, insert_error, name_conflict, validate_type_name, validate_type_patch,
+};
+use super::patch::Patch;
+use super::validation::{Validate, ValidatedJson, validate_non_negative};
+
+/// Unit harvest values of a harvest type are recorded in.
+#[derive(Serialize, Deserialize, TS, sqlx::Type, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+#[ts(export)]
+pub enum HarvestUnit {
+    #[default]
+    Bale,
+    Kg,
+    Tonne,
+    Litre,
+    CubicMetre,
+}
+
 #[derive(Serialize, Deserialize, FromRow, TS)]
 #[ts(export)]
 pub struct HarvestType {
     pub id: i32,
     pub name: String,
+    pub farm_id: i32,
+    /// Archived types are kept for existing harvests but can't be used for new ones.
+    pub archived: bool,
+    pub unit: HarvestUnit,
+    /// Weight of one unit, needed to report units other than kg and tonne by mass.
+    pub kg_per_unit: Option<f64>,
+    /// Dry matter percentage, needed to report as dry matter.
+    pub dry_matter_pct: Option<f64>,
+    /// Kilograms of nitrogen removed from the field per unit harvested.
+    pub n_removal_kg_per_unit: Option<f64>,
+    pub p_removal_kg_per_unit: Option<f64>,
+    pub k_removal_kg_per_unit: Option<f64>,
+    pub created_at: DateTime<Utc>,
+    pub created_by: Option<String>,
+    pub updated_at: DateTime<Utc>,
+    pub updated_by: Option<String>,
 }
 
-async fn get_types(State(pool): State<PgPool>) -> Result<impl IntoResponse, SorjordetError> {
-    let result: Vec<HarvestType> = query_as!(HarvestType, "SELECT id, name FROM harvest_type")
-        .fetch_all(&pool)
-        .await?;
+#[derive(Deserialize, TS)]
+#[ts(export)]
+pub struct CreateHarvestType {
+    pub name: String,
+    pub farm_id: i32,
+    #[serde(default)]
+    #[ts(optional)]
+    pub unit: Option<HarvestUnit>,
+    pub kg_per_unit: Option<f64>,
+    pub dry_matter_pct: Option<f64>,
+    pub n_removal_kg_per_unit: Option<f64>,
+    pub p_removal_kg_per_unit: Option<f64>,
+    pub k_removal_kg_per_unit: Option<f64>,
+}
+
+impl Validate for CreateHarvestType {
+    fn validate(&self) -> Result<(), SorjordetError> {
+        validate_type_name(&self.name)?;
+        validate_factors(self.kg_per_unit, self.dry_matter_pct)?;
+        validate_removal(
+            self.n_removal_kg_per_unit,
+            self.p_removal_kg_per_unit,
+            self.k_removal_kg_per_unit,
+        )
+    }
+}
+
+/// JSON merge patch for a harvest type, only the given members are changed.
+#[derive(Deserialize, TS)]
+#[ts(export)]
+pub struct UpdateHarvestType {
+    #[serde(default)]
+    #[ts(as = "Option<String>", optional)]
+    pub name: Patch<String>,
+    #[serde(default)]
+    #[ts(as = "Option<bool>", optional)]
+    pub archived: Patch<bool>,
+    /// Can only be changed while the type has no harvests.
+    #[serde(default)]
+    #[ts(as = "Option<HarvestUnit>", optional)]
+    pub unit: Patch<HarvestUnit>,
+    #[serde(default)]
+    #[ts(as = "Option<f64>", optional = nullable)]
+    pub kg_per_unit: Patch<f64>,
+    #[serde(default)]
+    #[ts(as = "Option<f64>", optional = nullable)]
+    pub dry_matter_pct: Patch<f64>,
+    #[serde(default)]
+    #[ts(as = "Option<f64>", optional = nullable)]
+    pub n_removal_kg_per_unit: Patch<f64>,
+    #[serde(default)]
+    #[ts(as = "Option<f64>", optional = nullable)]
+    pub p_removal_kg_per_unit: Patch<f64>,
+    #[serde(default)]
+    #[ts(as = "Option<f64>", optional = nullable)]
+    pub k_removal_kg_per_unit: Patch<f64>,
+}
+
+impl Validate for UpdateHarvestType {
+    fn validate(&self) -> Result<(), SorjordetError> {
+        validate_type_patch(&self.name, &self.archived)?;
+        self.unit.reject_null("unit")?;
+        validate_factors(
+            self.kg_per_unit.value().copied(),
+            self.dry_matter_pct.value().copied(),
+        )?;
+        validate_removal(
+            self.n_removal_kg_per_unit.value().copied(),
+            self.p_removal_kg_per_unit.value().copied(),
+            self.k_removal_kg_per_unit.value().copied(),
+        )
+    }
+}
+
+fn validate_factors(
+    kg_per_unit: Option<f64>,
+    dry_matter_pct: Option<f64>,
+) -> Result<(), SorjordetError> {
+    if kg_per_unit.is_some_and(|x| !x.is_finite() || x <= 0.0) {
+        return Err(SorjordetError::InvalidInput(
+            "kg_per_unit must be greater than 0".to_string(),
+        ));
+    }
+    if dry_matter_pct.is_some_and(|x| !(0.0..=100.0).contains(&x)) {
+        return Err(SorjordetError::InvalidInput(
+            "dry_matter_pct must be between 0 and 100".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_removal(n: Option<f64>, p: Option<f64>, k: Option<f64>) -> Result<(), SorjordetError> {
+    for (field, value) in [
+        ("n_removal_kg_per_unit", n),
+        ("p_removal_kg_per_unit", p),
+        ("k_removal_kg_per_unit", k),
+    ] {
+        if let Some(value) = value {
+            validate_non_negative(field, value)?;
+        }
+    }
+    Ok(())
+}
+
+/// Reassigns every harvest of this type to `into_type_id` and removes this type.
+#[derive(Deserialize, TS)]
+#[ts(export)]
+pub struct MergeHarvestType {
+    pub into_type_id: i32,
+}
+
+#[derive(Serialize, TS)]
+#[ts(export)]
+pub struct MergedHarvestType {
+    pub type_id: i32,
+    /// Number of harvest events moved to `type_id`.
+    pub moved_events: i64,
+}
+
+#[derive(Deserialize)]
+struct HarvestTypeParams {
+    farm_id: Option<i32>,
+    /// Include archived types, which are left out by default.
+    #[serde(default)]
+    include_archived: bool,
+}
+
+const WHAT: &str = "harvest type";
+
+async fn get_types(
+    State(pool): State<PgPool>,
+    extract::Query(params): extract::Query<HarvestTypeParams>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    let result: Vec<HarvestType> = query_as!(
+        HarvestType,
+        r#"SELECT id, name, farm_id, archived, unit as "unit: HarvestUnit", kg_per_unit, dry_matter_pct,
+                    n_removal_kg_per_unit, p_removal_kg_per_unit, k_removal_kg_per_unit,
+                    created_at, created_by, updated_at, updated_by
+                FROM harvest_type
+                WHERE ($1::int IS NULL OR farm_id = $1)
+                    AND ($2 OR NOT archived)
+                ORDER BY name
+            "#,
+        params.farm_id,
+        params.include_archived
+    )
+    .fetch_all(&pool)
+    .await?;
 
     Ok(Json(result))
 }
@@ -29,17 +210,29 @@ async fn get_types(State(pool): State<PgPool>) -> Result<impl IntoResponse, Sorj
 async fn post_type(
     claims: Claims,
     State(pool): State<PgPool>,
-    extract::Json(payload): extract::Json<HarvestType>,
+    ValidatedJson(payload): ValidatedJson<CreateHarvestType>,
 ) -> Result<impl IntoResponse, SorjordetError> {
     let result = query_scalar!(
-        "INSERT INTO harvest_type (name)
-                VALUES ($1)
+        "INSERT INTO harvest_type
+                    (name, farm_id, unit, kg_per_unit, dry_matter_pct,
+                    n_removal_kg_per_unit, p_removal_kg_per_unit, k_removal_kg_per_unit,
+                    created_by, updated_by)
+                VALUES ($1, $6, $2, $3, $4, $7, $8, $9, $5, $5)
                 RETURNING id
             ",
-        &payload.name
+        payload.name.trim(),
+        payload.unit.unwrap_or_default() as HarvestUnit,
+        payload.kg_per_unit,
+        payload.dry_matter_pct,
+        &claims.sub,
+        payload.farm_id,
+        payload.n_removal_kg_per_unit,
+        payload.p_removal_kg_per_unit,
+        payload.k_removal_kg_per_unit
     )
     .fetch_one(&pool)
-    .await?;
+    .await
+    .map_err(insert_error(WHAT, payload.farm_id))?;
 
     tracing::info!("new harvest_type inserted by {}", claims.sub);
 
@@ -50,18 +243,63 @@ async fn patch_type(
     claims: Claims,
     State(pool): State<PgPool>,
     extract::Path(type_id): extract::Path<i32>,
-    extract::Json(payload): extract::Json<HarvestType>,
+    ValidatedJson(payload): ValidatedJson<UpdateHarvestType>,
 ) -> Result<impl IntoResponse, SorjordetError> {
+    let mut tx = pool.begin().await?;
+
+    // recorded values would change meaning with the unit, the lock keeps new harvests out
+    if let Some(unit) = payload.unit.value() {
+        let current = query!(
+            r#"SELECT unit as "unit: HarvestUnit",
+                        EXISTS(SELECT 1 FROM harvest_event WHERE harvest_type_id = $1) as "used!"
+                    FROM harvest_type
+                    WHERE id = $1
+                    FOR UPDATE
+                "#,
+            type_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        if current.is_some_and(|x| x.used && x.unit != *unit) {
+            return Err(SorjordetError::Conflict(format!(
+                "harvest_type with id {type_id} has harvests, its unit can not be changed"
+            )));
+        }
+    }
+
     let result = query!(
         "UPDATE harvest_type
-                SET name = $1
+                SET name = COALESCE($1, name),
+                    archived = COALESCE($9, archived),
+                    unit = COALESCE($4, unit),
+                    kg_per_unit = CASE WHEN $5 THEN $6 ELSE kg_per_unit END,
+                    dry_matter_pct = CASE WHEN $7 THEN $8 ELSE dry_matter_pct END,
+                    n_removal_kg_per_unit = CASE WHEN $10 THEN $11 ELSE n_removal_kg_per_unit END,
+                    p_removal_kg_per_unit = CASE WHEN $12 THEN $13 ELSE p_removal_kg_per_unit END,
+                    k_removal_kg_per_unit = CASE WHEN $14 THEN $15 ELSE k_removal_kg_per_unit END,
+                    updated_at = NOW(),
+                    updated_by = $3
                 WHERE id = $2
             ",
-        &payload.name,
-        &type_id
+        payload.name.value().map(|x| x.trim()),
+        &type_id,
+        &claims.sub,
+        payload.unit.value().copied() as Option<HarvestUnit>,
+        payload.kg_per_unit.is_set(),
+        payload.kg_per_unit.value(),
+        payload.dry_matter_pct.is_set(),
+        payload.dry_matter_pct.value(),
+        payload.archived.value(),
+        payload.n_removal_kg_per_unit.is_set(),
+        payload.n_removal_kg_per_unit.value(),
+        payload.p_removal_kg_per_unit.is_set(),
+        payload.p_removal_kg_per_unit.value(),
+        payload.k_removal_kg_per_unit.is_set(),
+        payload.k_removal_kg_per_unit.value()
     )
-    .execute(&pool)
-    .await?;
+    .execute(&mut *tx)
+    .await
+    .map_err(name_conflict(WHAT))?;
 
     if result.rows_affected() == 0 {
         tracing::info!("harvest_type {} not found", type_id);
@@ -71,13 +309,84 @@ async fn patch_type(
         )));
     }
 
+    tx.commit().await?;
+
     tracing::info!("harvest_type {type_id} updated by {}", claims.sub);
 
     Ok(())
 }
 
+async fn merge_type(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    extract::Path(type_id): extract::Path<i32>,
+    Json(payload): Json<MergeHarvestType>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    let into_type_id = payload.into_type_id;
+    let mut tx = pool.begin().await?;
+
+    let types = query!(
+        r#"SELECT id, farm_id, unit as "unit: HarvestUnit"
+                FROM harvest_type
+                WHERE id = $1 OR id = $2
+                ORDER BY id
+                FOR UPDATE
+            "#,
+        type_id,
+        into_type_id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let find = |id| types.iter().find(|x| x.id == id);
+    check_merge(
+        WHAT,
+        type_id,
+        into_type_id,
+        find(type_id).map(|x| x.farm_id),
+        find(into_type_id).map(|x| x.farm_id),
+    )?;
+    if find(type_id).map(|x| x.unit) != find(into_type_id).map(|x| x.unit) {
+        return Err(SorjordetError::InvalidInput(
+            "harvest types with different units can not be merged".to_string(),
+        ));
+    }
+
+    let moved = query!(
+        "UPDATE harvest_event
+                SET harvest_type_id = $2,
+                    version = version + 1,
+                    updated_at = NOW(),
+                    updated_by = $3
+                WHERE harvest_type_id = $1
+            ",
+        type_id,
+        into_type_id,
+        &claims.sub
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    query!("DELETE FROM harvest_type WHERE id = $1", type_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    tracing::info!(
+        "harvest_type {type_id} merged into {into_type_id} by {}",
+        claims.sub
+    );
+
+    Ok(Json(MergedHarvestType {
+        type_id: into_type_id,
+        moved_events: moved.rows_affected() as i64,
+    }))
+}
+
 pub fn harvest_type_router() -> Router<PgPool> {
     Router::new()
+        .route("/{type_id}/merge", post(merge_type))
         .route("/{type_id}", patch(patch_type))
         .route("/", get(get_types).post(post_type))
 }
@@ -1,20 +1,39 @@
+mod catalogue;
+mod crop_planting;
+mod etag;
 mod farm;
 mod farm_field;
 mod farm_field_group;
+mod field_application;
 mod field_event;
+mod field_event_type;
+mod field_import;
+mod field_tag;
 mod harvest_event;
 mod harvest_type;
+mod patch;
+mod period;
+mod tiles;
+mod track_import;
 mod users;
+mod validation;
 
 use crate::auth::login_user;
 use axum::{Router, routing::post};
+use crop_planting::crop_planting_router;
 use farm::farm_router;
 use farm_field::farm_field_router;
 use farm_field_group::farm_field_group_router;
+use field_application::field_application_router;
 use field_event::field_event_router;
+use field_event_type::field_event_type_router;
+use field_import::field_import_router;
+use field_tag::field_tag_router;
 use harvest_event::harvest_event_router;
 use harvest_type::harvest_type_router;
 use sqlx::PgPool;
+use tiles::tiles_router;
+use track_import::track_import_router;
 
 async fn fallback() -> impl axum::response::IntoResponse {
     (axum::http::StatusCode::NOT_FOUND, "Not Found")
@@ -23,11 +42,20 @@ async fn fallback() -> impl axum::response::IntoResponse {
 pub async fn api_router(pg_pool: PgPool) -> Router {
     Router::new()
         .nest("/field_event", field_event_router())
+        .nest("/field_event_type", field_event_type_router())
+        .nest("/field_application", field_application_router())
         .nest("/harvest_type", harvest_type_router())
         .nest("/harvest_event", harvest_event_router())
-        .nest("/farm_fields", farm_field_router())
+        .nest(
+            "/farm_fields",
+            farm_field_router().merge(field_import_router()),
+        )
         .nest("/farm_field_groups", farm_field_group_router())
+        .nest("/field_tags", field_tag_router())
         .nest("/farm", farm_router())
+        .nest("/crop_planting", crop_planting_router())
+        .nest("/tiles", tiles_router())
+        .nest("/track_import", track_import_router())
         .nest("/users", users::users_router())
         .nest("/auth", Router::new().route("/login", post(login_user)))
         .with_state(pg_pool)
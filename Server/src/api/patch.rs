@@ -0,0 +1,55 @@
+use serde::{Deserialize, Deserializer};
+
+use crate::errors::SorjordetError;
+
+/// A single member of a JSON merge patch (RFC 7396).
+///
+/// A missing member leaves the column untouched, `null` clears it
+/// and any other value replaces it. Use with `#[serde(default)]`.
+#[derive(Default)]
+pub enum Patch<T> {
+    #[default]
+    Missing,
+    Null,
+    Value(T),
+}
+
+impl<T> Patch<T> {
+    /// The new value, if one was given.
+    pub fn value(&self) -> Option<&T> {
+        match self {
+            Patch::Value(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    /// Whether the column should be written, either with a new value or with NULL.
+    pub fn is_set(&self) -> bool {
+        !matches!(self, Patch::Missing)
+    }
+
+    /// NOT NULL columns can only be replaced, not removed.
+    pub fn reject_null(&self, field: &str) -> Result<(), SorjordetError> {
+        match self {
+            Patch::Null => Err(SorjordetError::InvalidInput(format!(
+                "{field} can not be null"
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Patch<T>
+where
+    T: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(match Option::<T>::deserialize(deserializer)? {
+            Some(x) => Patch::Value(x),
+            None => Patch::Null,
+        })
+    }
+}
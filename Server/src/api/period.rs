@@ -0,0 +1,343 @@
+use chrono::{Datelike, Days, Months, NaiveDate};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::errors::SorjordetError;
+
+/// Limit on the periods of a zero-filled series, ten years of days.
+pub const MAX_PERIODS: usize = 3660;
+
+/// Length of the periods harvest statistics are bucketed into.
+#[derive(Deserialize, Serialize, TS, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum Granularity {
+    Day,
+    Week,
+    #[default]
+    Month,
+    Season,
+    Year,
+}
+
+/// First day of a farming season, as month and day of month.
+/// Seasons starting after January 1st straddle two calendar years.
+#[derive(Deserialize, Clone, Copy)]
+#[serde(try_from = "String")]
+pub struct SeasonStart {
+    month: u32,
+    day: u32,
+}
+
+impl Default for SeasonStart {
+    fn default() -> Self {
+        SeasonStart { month: 1, day: 1 }
+    }
+}
+
+impl TryFrom<String> for SeasonStart {
+    type Error = String;
+
+    /// Parses `MM-DD`, e.g. `04-01` for seasons starting April 1st.
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let invalid = || format!("season start {value} must be a valid MM-DD date");
+        let (month, day) = value.split_once('-').ok_or_else(invalid)?;
+        let month = month.parse::<u32>().map_err(|_| invalid())?;
+        let day = day.parse::<u32>().map_err(|_| invalid())?;
+
+        SeasonStart::new(month, day).ok_or_else(invalid)
+    }
+}
+
+impl SeasonStart {
+    /// `None` unless the day exists in every year, which rules out February 29th.
+    pub fn new(month: u32, day: u32) -> Option<Self> {
+        NaiveDate::from_ymd_opt(2001, month, day).map(|_| SeasonStart { month, day })
+    }
+
+    pub fn month(&self) -> u32 {
+        self.month
+    }
+
+    pub fn day(&self) -> u32 {
+        self.day
+    }
+
+    /// The season a date belongs to, named by the calendar year the season starts in.
+    pub fn season_of(&self, date: NaiveDate) -> i32 {
+        if (date.month(), date.day()) >= (self.month, self.day) {
+            date.year()
+        } else {
+            date.year() - 1
+        }
+    }
+
+    pub fn start_of(&self, season: i32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(season, self.month, self.day)
+            .expect("season start is validated to exist in every year")
+    }
+
+    /// `2024` for calendar year seasons, `2024/2025` for seasons straddling two years.
+    pub fn label(&self, season: i32) -> String {
+        if (self.month, self.day) == (1, 1) {
+            format!("{season}")
+        } else {
+            format!("{season}/{}", season + 1)
+        }
+    }
+}
+
+impl Granularity {
+    /// First day of the period containing `date`.
+    pub fn period_start(&self, date: NaiveDate, season: SeasonStart) -> NaiveDate {
+        match self {
+            Granularity::Day => date,
+            Granularity::Week => date - Days::new(date.weekday().num_days_from_monday() as u64),
+            Granularity::Month => date.with_day(1).unwrap(),
+            Granularity::Season => season.start_of(season.season_of(date)),
+            Granularity::Year => date.with_day(1).unwrap().with_month(1).unwrap(),
+        }
+    }
+
+    /// First day of the period following the one starting at `start`, `None` past the last date.
+    pub fn next_period(&self, start: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            Granularity::Day => start.checked_add_days(Days::new(1)),
+            Granularity::Week => start.checked_add_days(Days::new(7)),
+            Granularity::Month => start.checked_add_months(Months::new(1)),
+            Granularity::Season | Granularity::Year => start.checked_add_months(Months::new(12)),
+        }
+    }
+
+    /// ISO 8601 style key for the period starting at `start`, keys sort chronologically.
+    pub fn key(&self, start: NaiveDate, season: SeasonStart) -> String {
+        match self {
+            Granularity::Day => start.format("%Y-%m-%d").to_string(),
+            Granularity::Week => {
+                let week = start.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            }
+            Granularity::Month => start.format("%Y-%m").to_string(),
+            Granularity::Season => season.label(season.season_of(start)),
+            Granularity::Year => start.format("%Y").to_string(),
+        }
+    }
+
+    /// Start of every period from the one containing `from` up to and including the one containing `to`.
+    /// Ranges of more than `MAX_PERIODS` periods are rejected rather than filled with zeros.
+    pub fn periods(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+        season: SeasonStart,
+    ) -> Result<Vec<NaiveDate>, SorjordetError> {
+        let mut periods = vec![];
+        let mut start = Some(self.period_start(from, season));
+        while let Some(period) = start.filter(|x| *x <= to) {
+            if periods.len() == MAX_PERIODS {
+                return Err(SorjordetError::InvalidInput(format!(
+                    "from and to span more than {MAX_PERIODS} periods, use a shorter range or a longer granularity"
+                )));
+            }
+            periods.push(period);
+            start = self.next_period(period);
+        }
+        Ok(periods)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn keys(granularity: Granularity, from: NaiveDate, to: NaiveDate) -> Vec<String> {
+        let season = SeasonStart::default();
+        granularity
+            .periods(from, to, season)
+            .unwrap()
+            .into_iter()
+            .map(|x| granularity.key(x, season))
+            .collect()
+    }
+
+    #[test]
+    fn week_keys_across_year_end() {
+        // 2020 has 53 ISO weeks, the week of January 1st 2021 is still week 53
+        assert_eq!(
+            keys(Granularity::Week, date(2020, 12, 24), date(2021, 1, 5)),
+            ["2020-W52", "2020-W53", "2021-W01"]
+        );
+        // December 30th 2024 is in the first week of 2025
+        assert_eq!(
+            keys(Granularity::Week, date(2024, 12, 22), date(2025, 1, 1)),
+            ["2024-W51", "2024-W52", "2025-W01"]
+        );
+        assert_eq!(
+            Granularity::Week.period_start(date(2021, 1, 1), SeasonStart::default()),
+            date(2020, 12, 28)
+        );
+    }
+
+    #[test]
+    fn month_keys_are_zero_padded() {
+        assert_eq!(
+            keys(Granularity::Month, date(2024, 8, 15), date(2024, 11, 2)),
+            ["2024-08", "2024-09", "2024-10", "2024-11"]
+        );
+        assert_eq!(
+            keys(Granularity::Day, date(2024, 2, 28), date(2024, 3, 1)),
+            ["2024-02-28", "2024-02-29", "2024-03-01"]
+        );
+    }
+
+    #[test]
+    fn periods_are_zero_filled_in_order() {
+        let season = SeasonStart::default();
+        let periods = Granularity::Month
+            .periods(date(2023, 11, 20), date(2024, 2, 1), season)
+            .unwrap();
+        assert_eq!(
+            periods,
+            [
+                date(2023, 11, 1),
+                date(2023, 12, 1),
+                date(2024, 1, 1),
+                date(2024, 2, 1)
+            ]
+        );
+
+        let weeks = Granularity::Week
+            .periods(date(2020, 1, 1), date(2023, 12, 31), season)
+            .unwrap();
+        assert!(
+            weeks
+                .windows(2)
+                .all(|x| x[1] - x[0] == chrono::Duration::days(7))
+        );
+        let keys: Vec<String> = weeks
+            .iter()
+            .map(|x| Granularity::Week.key(*x, season))
+            .collect();
+        assert!(keys.windows(2).all(|x| x[0] < x[1]));
+    }
+
+    #[test]
+    fn periods_before_from_are_empty() {
+        let periods = Granularity::Day
+            .periods(date(2024, 5, 2), date(2024, 5, 1), SeasonStart::default())
+            .unwrap();
+        assert!(periods.is_empty());
+    }
+
+    #[test]
+    fn season_start_must_exist_every_year() {
+        assert!(SeasonStart::new(8, 1).is_some());
+        assert!(SeasonStart::new(12, 31).is_some());
+        assert!(SeasonStart::new(2, 29).is_none());
+        assert!(SeasonStart::new(4, 31).is_none());
+        assert!(SeasonStart::new(13, 1).is_none());
+        assert!(SeasonStart::try_from("08-01".to_string()).is_ok());
+        assert!(SeasonStart::try_from("02-29".to_string()).is_err());
+        assert!(SeasonStart::try_from("0801".to_string()).is_err());
+    }
+
+    #[test]
+    fn seasons_straddling_the_year() {
+        let season = SeasonStart::new(8, 1).unwrap();
+        assert_eq!(season.season_of(date(2024, 7, 31)), 2023);
+        assert_eq!(season.season_of(date(2024, 8, 1)), 2024);
+        assert_eq!(season.season_of(date(2024, 12, 31)), 2024);
+        assert_eq!(season.season_of(date(2025, 2, 28)), 2024);
+        assert_eq!(season.start_of(2024), date(2024, 8, 1));
+        assert_eq!(season.label(2024), "2024/2025");
+        assert_eq!(
+            Granularity::Season.period_start(date(2025, 3, 1), season),
+            date(2024, 8, 1)
+        );
+        assert_eq!(
+            Granularity::Season
+                .periods(date(2023, 9, 1), date(2025, 8, 1), season)
+                .unwrap()
+                .into_iter()
+                .map(|x| Granularity::Season.key(x, season))
+                .collect::<Vec<_>>(),
+            ["2023/2024", "2024/2025", "2025/2026"]
+        );
+    }
+
+    #[test]
+    fn calendar_year_seasons() {
+        let season = SeasonStart::default();
+        assert_eq!(season.season_of(date(2024, 1, 1)), 2024);
+        assert_eq!(season.season_of(date(2023, 12, 31)), 2023);
+        assert_eq!(season.season_of(date(2024, 2, 29)), 2024);
+        assert_eq!(season.label(2024), "2024");
+    }
+
+    /// `farm_season()` buckets the same harvests in SQL, it must agree with `season_of`.
+    /// Needs a migrated database in `DATABASE_URL` and is skipped without one.
+    #[tokio::test]
+    async fn farm_season_agrees_with_season_of() {
+        let Ok(url) = std::env::var("DATABASE_URL") else {
+            return;
+        };
+        let pool = sqlx::PgPool::connect(&url).await.unwrap();
+
+        let starts = [(1, 1), (3, 1), (8, 1), (12, 31)];
+        let dates = [
+            date(2023, 12, 31),
+            date(2024, 1, 1),
+            date(2024, 2, 28),
+            date(2024, 2, 29),
+            date(2024, 3, 1),
+            date(2024, 7, 31),
+            date(2024, 8, 1),
+            date(2024, 12, 30),
+            date(2024, 12, 31),
+        ];
+        for (month, day) in starts {
+            let season = SeasonStart::new(month, day).unwrap();
+            for date in dates {
+                let time = date.and_hms_opt(12, 0, 0).unwrap().and_utc();
+                let in_sql: i32 = sqlx::query_scalar("SELECT farm_season($1, $2, $3)")
+                    .bind(time)
+                    .bind(month as i32)
+                    .bind(day as i32)
+                    .fetch_one(&pool)
+                    .await
+                    .unwrap();
+                assert_eq!(
+                    in_sql,
+                    season.season_of(date),
+                    "{date} with start {month}-{day}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn periods_are_capped() {
+        let season = SeasonStart::default();
+        let from = date(2000, 1, 1);
+        let last = from + Days::new(MAX_PERIODS as u64 - 1);
+        assert_eq!(
+            Granularity::Day.periods(from, last, season).unwrap().len(),
+            MAX_PERIODS
+        );
+        assert!(matches!(
+            Granularity::Day.periods(from, last + Days::new(1), season),
+            Err(SorjordetError::InvalidInput(_))
+        ));
+        assert_eq!(
+            Granularity::Year
+                .periods(from, date(2099, 12, 31), season)
+                .unwrap()
+                .len(),
+            100
+        );
+    }
+}
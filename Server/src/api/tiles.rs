@@ -0,0 +1,124 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use axum::{
+    self, Router,
+    extract::{self, State},
+    http::{
+        HeaderMap, HeaderName, StatusCode,
+        header::{CACHE_CONTROL, CONTENT_TYPE, ETAG, IF_NONE_MATCH},
+    },
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use serde::Deserialize;
+use sqlx::{PgPool, query_scalar};
+
+use crate::errors::SorjordetError;
+
+const MAX_ZOOM: i32 = 22;
+
+/// Tiles change whenever a field is edited or harvested, so they are only
+/// cached briefly and then revalidated against their `ETag`.
+const TILE_CACHE_CONTROL: &str = "public, max-age=300, must-revalidate";
+
+const MVT_CONTENT_TYPE: &str = "application/vnd.mapbox-vector-tile";
+
+#[derive(Deserialize)]
+struct TileParams {
+    farm_id: Option<i32>,
+}
+
+/// Mapbox Vector Tile with a `fields` layer, with the name, group colour and latest
+/// harvest of each field, and a `groups` layer with the outline of each field group.
+async fn get_tile(
+    State(pool): State<PgPool>,
+    extract::Path((z, x, tile)): extract::Path<(i32, i32, String)>,
+    extract::Query(params): extract::Query<TileParams>,
+    headers: HeaderMap,
+) -> Result<Response, SorjordetError> {
+    let Some(y) = tile
+        .strip_suffix(".mvt")
+        .and_then(|y| y.parse::<i32>().ok())
+    else {
+        return Err(SorjordetError::NotFound(format!("tile {tile} not found")));
+    };
+    if !(0..=MAX_ZOOM).contains(&z) || !(0..1 << z).contains(&x) || !(0..1 << z).contains(&y) {
+        return Err(SorjordetError::InvalidInput(format!(
+            "tile {z}/{x}/{y} is outside the map"
+        )));
+    }
+
+    let tile = query_scalar!(
+        r#"
+        WITH bounds AS (
+            SELECT ST_TileEnvelope($1, $2, $3) AS tile,
+                ST_Transform(ST_TileEnvelope($1, $2, $3), 4326) AS wgs84
+        ),
+        fields AS (
+            SELECT f.id, f.name, f.farm_id, f.farm_field_group_id AS group_id,
+                g.name AS group_name, g.draw_color AS group_color,
+                h.time::text AS latest_harvest_time, h.value AS latest_harvest_value,
+                t.name AS latest_harvest_type, t.unit AS latest_harvest_unit,
+                ST_AsMVTGeom(ST_Transform(f.boundary, 3857), b.tile) AS geom
+            FROM farm_field f
+                CROSS JOIN bounds b
+                LEFT JOIN farm_field_group g ON g.id = f.farm_field_group_id
+                LEFT JOIN LATERAL (
+                    SELECT e.time, e.value, e.harvest_type_id
+                    FROM harvest_event e
+                    WHERE e.field_id = f.id
+                    ORDER BY e.time DESC
+                    LIMIT 1
+                ) h ON TRUE
+                LEFT JOIN harvest_type t ON t.id = h.harvest_type_id
+            WHERE f.retired_at IS NULL
+                AND ST_Intersects(f.boundary, b.wgs84)
+                AND ($4::int IS NULL OR f.farm_id = $4)
+        ),
+        groups AS (
+            SELECT g.id, g.name, g.farm_id, g.draw_color AS color,
+                ST_AsMVTGeom(ST_Transform(ST_Union(f.boundary), 3857), b.tile) AS geom
+            FROM farm_field_group g
+                JOIN farm_field f ON f.farm_field_group_id = g.id AND f.retired_at IS NULL
+                CROSS JOIN bounds b
+            WHERE ST_Intersects(f.boundary, b.wgs84)
+                AND ($4::int IS NULL OR g.farm_id = $4)
+            GROUP BY g.id, g.name, g.farm_id, g.draw_color, b.tile
+        )
+        SELECT COALESCE((SELECT ST_AsMVT(fields, 'fields', 4096, 'geom') FROM fields WHERE geom IS NOT NULL), '')
+            || COALESCE((SELECT ST_AsMVT(groups, 'groups', 4096, 'geom') FROM groups WHERE geom IS NOT NULL), '')
+            as "tile!"
+    "#,
+        z,
+        x,
+        y,
+        params.farm_id
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    let mut hasher = DefaultHasher::new();
+    tile.hash(&mut hasher);
+    let etag = format!("\"{:016x}\"", hasher.finish());
+
+    let not_modified = headers
+        .get(IF_NONE_MATCH)
+        .and_then(|x| x.to_str().ok())
+        .is_some_and(|x| x.split(',').any(|tag| tag.trim() == etag));
+
+    let response_headers: [(HeaderName, String); 3] = [
+        (CONTENT_TYPE, MVT_CONTENT_TYPE.to_string()),
+        (CACHE_CONTROL, TILE_CACHE_CONTROL.to_string()),
+        (ETAG, etag),
+    ];
+
+    if not_modified {
+        return Ok((StatusCode::NOT_MODIFIED, response_headers).into_response());
+    }
+
+    Ok((response_headers, tile).into_response())
+}
+
+pub fn tiles_router() -> Router<PgPool> {
+    Router::new().route("/{z}/{x}/{tile}", get(get_tile))
+}
@@ -0,0 +1,395 @@
+use std::collections::BTreeMap;
+
+use axum::{
+    self, Json, Router,
+    body::Bytes,
+    extract::{self, DefaultBodyLimit, State},
+    response::IntoResponse,
+    routing::post,
+};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, query, query_scalar};
+use ts_rs::TS;
+
+use crate::auth::Claims;
+use crate::errors::SorjordetError;
+
+use super::farm_field::ensure_active_field;
+use super::field_event::CreateFieldEvent;
+use super::field_event_type::resolve_event_type;
+use super::field_import::{attribute, decode_text, elements, invalid_file, xml_text};
+use super::harvest_event::{CreateHarvestEvent, ensure_active_type};
+use super::validation::{Validate, ValidatedJson, validate_name};
+
+/// A day of logging at one point a second is a few megabytes of GPX.
+const MAX_TRACK_BYTES: usize = 32 * 1024 * 1024;
+
+/// Longer pauses between track points, like the logger being off, don't count as time worked.
+const MAX_POINT_GAP: TimeDelta = TimeDelta::minutes(5);
+
+/// Visits shorter than this are usually just driving across the field.
+const DEFAULT_MIN_MINUTES: f64 = 2.0;
+
+#[derive(Deserialize)]
+struct TrackParams {
+    farm_id: i32,
+    /// Leave out fields worked for less than this, defaults to 2 minutes.
+    min_minutes: Option<f64>,
+    /// Propose field events of this type.
+    event_type_id: Option<i32>,
+    /// Propose field events of the type with this name, see `CreateFieldEvent::event_name`.
+    event_name: Option<String>,
+    /// Propose harvest events of this type.
+    harvest_type_id: Option<i32>,
+}
+
+impl TrackParams {
+    fn validate(&self) -> Result<(), SorjordetError> {
+        if let Some(event_name) = &self.event_name {
+            validate_name("event_name", event_name, 128)?;
+        }
+        if self.min_minutes.is_some_and(|x| !x.is_finite() || x < 0.0) {
+            return Err(SorjordetError::InvalidInput(
+                "min_minutes must be a number, not negative".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Time a track spent inside a field.
+#[derive(Serialize, TS)]
+#[ts(export)]
+pub struct FieldVisit {
+    pub field_id: i32,
+    pub field_name: String,
+    /// First track point inside the field.
+    pub start: DateTime<Utc>,
+    /// Last track point inside the field.
+    pub end: DateTime<Utc>,
+    /// Time between consecutive points inside the field, without pauses.
+    pub minutes: f64,
+    pub points: i32,
+}
+
+/// A proposed harvest, its amount has to be filled in before confirming.
+#[derive(Serialize, TS)]
+#[ts(export)]
+pub struct DraftHarvestEvent {
+    pub value: Option<f64>,
+    pub time: DateTime<Utc>,
+    pub field_id: i32,
+    pub type_id: i32,
+}
+
+#[derive(Serialize, TS)]
+#[ts(export)]
+pub struct TrackPreview {
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    pub points: i32,
+    /// Points outside every field of the farm, like on roads and in the yard.
+    pub points_outside: i32,
+    /// Fields worked, in the order they were entered.
+    pub visits: Vec<FieldVisit>,
+    /// One per visit when an event type was given.
+    pub field_events: Vec<CreateFieldEvent>,
+    /// One per visit when a harvest type was given.
+    pub harvest_events: Vec<DraftHarvestEvent>,
+}
+
+/// Drafts from a track preview as confirmed by the user.
+#[derive(Deserialize, TS)]
+#[ts(export)]
+pub struct ConfirmTrackEvents {
+    #[serde(default)]
+    pub field_events: Vec<CreateFieldEvent>,
+    #[serde(default)]
+    pub harvest_events: Vec<CreateHarvestEvent>,
+}
+
+impl Validate for ConfirmTrackEvents {
+    fn validate(&self) -> Result<(), SorjordetError> {
+        if self.field_events.is_empty() && self.harvest_events.is_empty() {
+            return Err(SorjordetError::InvalidInput(
+                "no events to create".to_string(),
+            ));
+        }
+        self.field_events.iter().try_for_each(Validate::validate)?;
+        self.harvest_events.iter().try_for_each(Validate::validate)
+    }
+}
+
+#[derive(Serialize, TS)]
+#[ts(export)]
+pub struct ConfirmedTrackEvents {
+    pub field_event_ids: Vec<i32>,
+    pub harvest_event_ids: Vec<i32>,
+}
+
+struct TrackPoint {
+    lon: f64,
+    lat: f64,
+    time: DateTime<Utc>,
+}
+
+/// Timestamped points of every track and route in a GPX file, in time order.
+/// Track points must have a time, route points without one are left out since
+/// routes are usually planned rather than driven.
+fn read_gpx(data: &[u8]) -> Result<Vec<TrackPoint>, SorjordetError> {
+    let gpx = decode_text(data);
+    if elements(&gpx, "gpx").is_empty() {
+        return Err(invalid_file("not a GPX file"));
+    }
+
+    let read_point = |attributes: &str, point: &str| {
+        let coordinate = |name| {
+            attribute(attributes, name)
+                .and_then(|x| x.parse::<f64>().ok())
+                .ok_or_else(|| invalid_file(format!("track point without {name}")))
+        };
+        let time = elements(point, "time")
+            .first()
+            .and_then(|(_, x)| DateTime::parse_from_rfc3339(&xml_text(x)).ok())
+            .map(|x| x.with_timezone(&Utc));
+        Ok::<_, SorjordetError>((coordinate("lon")?, coordinate("lat")?, time))
+    };
+
+    let mut points = Vec::new();
+    for (attributes, point) in elements(&gpx, "trkpt") {
+        let (lon, lat, time) = read_point(attributes, point)?;
+        let time = time.ok_or_else(|| invalid_file("track points must have a time"))?;
+        points.push(TrackPoint { lon, lat, time });
+    }
+    for (attributes, point) in elements(&gpx, "rtept") {
+        if let (lon, lat, Some(time)) = read_point(attributes, point)? {
+            points.push(TrackPoint { lon, lat, time });
+        }
+    }
+    if points.is_empty() {
+        return Err(invalid_file("no track points with a time"));
+    }
+
+    points.sort_by_key(|x| x.time);
+    Ok(points)
+}
+
+/// Works out which fields of the farm a GPX track worked and for how long,
+/// and proposes events for them without saving anything.
+async fn preview_track(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    extract::Query(params): extract::Query<TrackParams>,
+    body: Bytes,
+) -> Result<impl IntoResponse, SorjordetError> {
+    params.validate()?;
+    let points = read_gpx(&body)?;
+
+    tracing::info!("track preview of {} points by {}", points.len(), claims.sub);
+
+    let lons: Vec<f64> = points.iter().map(|x| x.lon).collect();
+    let lats: Vec<f64> = points.iter().map(|x| x.lat).collect();
+    let located = query!(
+        r#"SELECT f.id as "field_id?", f.name as "field_name?"
+                FROM UNNEST($1::float8[], $2::float8[]) WITH ORDINALITY AS p(lon, lat, index)
+                    LEFT JOIN LATERAL (
+                        SELECT id, name
+                        FROM farm_field
+                        WHERE farm_id = $3
+                            AND retired_at IS NULL
+                            AND ST_Intersects(boundary, ST_SetSRID(ST_Point(p.lon, p.lat), 4326))
+                        ORDER BY id
+                        LIMIT 1
+                    ) f ON TRUE
+                ORDER BY p.index
+            "#,
+        &lons,
+        &lats,
+        params.farm_id
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    let mut visits = BTreeMap::<i32, FieldVisit>::new();
+    let mut points_outside = 0;
+    for (i, (point, location)) in points.iter().zip(&located).enumerate() {
+        let (Some(field_id), Some(field_name)) = (location.field_id, &location.field_name) else {
+            points_outside += 1;
+            continue;
+        };
+        let visit = visits.entry(field_id).or_insert_with(|| FieldVisit {
+            field_id,
+            field_name: field_name.clone(),
+            start: point.time,
+            end: point.time,
+            minutes: 0.0,
+            points: 0,
+        });
+        visit.end = point.time;
+        visit.points += 1;
+
+        let previous = i.checked_sub(1).map(|j| (&points[j], located[j].field_id));
+        if let Some((previous, Some(previous_field))) = previous {
+            let gap = point.time - previous.time;
+            if previous_field == field_id && gap <= MAX_POINT_GAP {
+                visit.minutes += gap.num_milliseconds() as f64 / 60_000.0;
+            }
+        }
+    }
+
+    let min_minutes = params.min_minutes.unwrap_or(DEFAULT_MIN_MINUTES);
+    let mut visits: Vec<FieldVisit> = visits
+        .into_values()
+        .filter(|x| x.minutes >= min_minutes)
+        .collect();
+    visits.sort_by_key(|x| x.start);
+
+    let field_events = if params.event_type_id.is_some() || params.event_name.is_some() {
+        visits
+            .iter()
+            .map(|x| CreateFieldEvent {
+                time: x.start,
+                field_id: x.field_id,
+                type_id: params.event_type_id,
+                event_name: params.event_name.clone(),
+                description: Some(format!(
+                    "{:.0} min from {} to {} (GPS track)",
+                    x.minutes,
+                    x.start.format("%H:%M"),
+                    x.end.format("%H:%M")
+                )),
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let harvest_events = match params.harvest_type_id {
+        Some(type_id) => visits
+            .iter()
+            .map(|x| DraftHarvestEvent {
+                value: None,
+                time: x.end,
+                field_id: x.field_id,
+                type_id,
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+
+    Ok(Json(TrackPreview {
+        start: points.first().map(|x| x.time),
+        end: points.last().map(|x| x.time),
+        points: points.len() as i32,
+        points_outside,
+        visits,
+        field_events,
+        harvest_events,
+    }))
+}
+
+/// Creates the confirmed drafts of a track preview, all of them or none.
+async fn confirm_track(
+    claims: Claims,
+    State(pool): State<PgPool>,
+    ValidatedJson(payload): ValidatedJson<ConfirmTrackEvents>,
+) -> Result<impl IntoResponse, SorjordetError> {
+    for event in &payload.field_events {
+        ensure_active_field(&pool, event.field_id).await?;
+    }
+    for event in &payload.harvest_events {
+        ensure_active_field(&pool, event.field_id).await?;
+        ensure_active_type(&pool, event.type_id, event.field_id).await?;
+    }
+
+    let mut tx = pool.begin().await?;
+
+    let mut field_event_ids = Vec::with_capacity(payload.field_events.len());
+    for event in &payload.field_events {
+        let type_id = resolve_event_type(
+            &mut tx,
+            event.field_id,
+            event.type_id,
+            event.event_name.as_deref(),
+            &claims.sub,
+        )
+        .await?;
+        let id = query_scalar!(
+            "INSERT INTO field_event
+                        (time, field_id, type_id, description, created_by, updated_by)
+                    VALUES ($1, $2, $3, $4, $5, $5)
+                    RETURNING id
+                ",
+            &event.time,
+            &event.field_id,
+            type_id,
+            event.description.as_deref().unwrap_or_default(),
+            &claims.sub
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        field_event_ids.push(id);
+    }
+
+    let mut harvest_event_ids = Vec::with_capacity(payload.harvest_events.len());
+    for event in &payload.harvest_events {
+        let id = query_scalar!(
+            "INSERT INTO harvest_event
+                        (value, time, field_id, harvest_type_id, created_by, updated_by)
+                    VALUES ($1, $2, $3, $4, $5, $5)
+                    RETURNING id
+                ",
+            &event.value,
+            &event.time,
+            &event.field_id,
+            &event.type_id,
+            &claims.sub
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        harvest_event_ids.push(id);
+    }
+
+    tx.commit().await?;
+
+    tracing::info!(
+        "{} field events and {} harvest events from a track inserted by {}",
+        field_event_ids.len(),
+        harvest_event_ids.len(),
+        claims.sub
+    );
+
+    Ok(Json(ConfirmedTrackEvents {
+        field_event_ids,
+        harvest_event_ids,
+    }))
+}
+
+pub fn track_import_router() -> Router<PgPool> {
+    Router::new()
+        .route(
+            "/preview",
+            post(preview_track).layer(DefaultBodyLimit::max(MAX_TRACK_BYTES)),
+        )
+        .route("/confirm", post(confirm_track))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gpx_route_points_without_time() {
+        let gpx = br#"<gpx><rte><rtept lat="59.1" lon="10.1"></rtept></rte><trk><trkseg>
+            <trkpt lat='59.2' lon='10.2'><time>2026-05-01T10:01:00Z</time></trkpt>
+            <trkpt lat="59.3" lon="10.3"><time>2026-05-01T10:00:00Z</time></trkpt>
+            </trkseg></trk></gpx>"#;
+        let points = read_gpx(gpx).unwrap();
+        let coordinates: Vec<_> = points.iter().map(|x| (x.lon, x.lat)).collect();
+        assert_eq!(coordinates, [(10.3, 59.3), (10.2, 59.2)]);
+
+        assert!(read_gpx(br#"<gpx><trk><trkpt lat="59" lon="10"></trkpt></trk></gpx>"#).is_err());
+        assert!(read_gpx(br#"<gpx><rte><rtept lat="59" lon="10"></rtept></rte></gpx>"#).is_err());
+    }
+}
@@ -10,9 +10,12 @@ use sqlx::{FromRow, PgPool, query, query_as, query_scalar};
 use ts_rs::TS;
 
 use crate::{
-    auth::{Claims, User, hash_password, validate_password},
+    auth::{Claims, hash_password, validate_password},
     errors::SorjordetError,
 };
+
+use super::patch::Patch;
+use super::validation::{Validate, ValidatedJson, validate_email, validate_name};
 lazy_static! {
     static ref PW_SECRET: String = std::env::var("PW_SECRET").expect("PW_SECRET must be set");
 }
@@ -25,22 +28,77 @@ pub struct UserInfo {
     pub email: String,
 }
 
+#[derive(Deserialize, TS)]
+#[ts(export)]
+pub struct CreateUser {
+    pub name: String,
+    pub password: String,
+    pub email: String,
+}
+
+impl Validate for CreateUser {
+    fn validate(&self) -> Result<(), SorjordetError> {
+        validate_name("name", &self.name, 256)?;
+        validate_email("email", &self.email, 256)?;
+        if !validate_password(&self.password) {
+            return Err(SorjordetError::InvalidInput(
+                "Password too weak".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// JSON merge patch for a user, only the given members are changed.
+/// An empty or null password leaves the current password unchanged.
+#[derive(Deserialize, TS)]
+#[ts(export)]
+pub struct UpdateUser {
+    #[serde(default)]
+    #[ts(as = "Option<String>", optional)]
+    pub name: Patch<String>,
+    #[serde(default)]
+    #[ts(as = "Option<String>", optional)]
+    pub email: Patch<String>,
+    #[serde(default)]
+    #[ts(as = "Option<String>", optional = nullable)]
+    pub password: Patch<String>,
+}
+
+impl UpdateUser {
+    fn new_password(&self) -> Option<&str> {
+        self.password
+            .value()
+            .map(String::as_str)
+            .filter(|p| !p.is_empty())
+    }
+}
+
+impl Validate for UpdateUser {
+    fn validate(&self) -> Result<(), SorjordetError> {
+        self.name.reject_null("name")?;
+        self.email.reject_null("email")?;
+        if let Some(name) = self.name.value() {
+            validate_name("name", name, 256)?;
+        }
+        if let Some(email) = self.email.value() {
+            validate_email("email", email, 256)?;
+        }
+        if self.new_password().is_some_and(|p| !validate_password(p)) {
+            return Err(SorjordetError::InvalidInput(
+                "Password too weak".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
 /// Function for registering new users.
 pub async fn create_user(
     claims: Claims,
     State(pool): State<PgPool>,
-    extract::Json(payload): extract::Json<User>,
+    ValidatedJson(payload): ValidatedJson<CreateUser>,
 ) -> Result<impl IntoResponse, SorjordetError> {
-    if payload.id != -1 {
-        return Err(SorjordetError::InvalidInput(
-            "User already exists".to_string(),
-        ));
-    }
-    if !validate_password(&payload.password) {
-        return Err(SorjordetError::InvalidInput(
-            "Password too weak".to_string(),
-        ));
-    }
     let hashed = hash_password(&payload.password)?;
     let now = chrono::Utc::now().naive_utc();
 
@@ -85,33 +143,24 @@ async fn patch_user(
     claims: Claims,
     State(pool): State<PgPool>,
     extract::Path(user_id): extract::Path<i32>,
-    extract::Json(payload): extract::Json<User>,
+    ValidatedJson(payload): ValidatedJson<UpdateUser>,
 ) -> Result<impl IntoResponse, SorjordetError> {
     // this is ok since username is unique in the database.
     // TODO: Change sub to userid and add name for this check instead.
-    let query = if claims.sub == "steinar" && validate_password(&payload.password) {
-        let hashed = hash_password(&payload.password)?;
-        query!(
-            "UPDATE user_info
-                SET name = $1, email = $2, password = $3
-                WHERE id = $4
-            ",
-            &payload.name,
-            &payload.email,
-            &hashed,
-            user_id
-        )
-    } else {
-        query!(
-            "UPDATE user_info
-                SET name = $1, email = $2
-                WHERE id = $3
-            ",
-            &payload.name,
-            &payload.email,
-            user_id
-        )
-    };
+    let new_password = payload.new_password().filter(|_| claims.sub == "steinar");
+    let hashed = new_password.map(hash_password).transpose()?;
+    let query = query!(
+        "UPDATE user_info
+            SET name = COALESCE($1, name),
+                email = COALESCE($2, email),
+                password = COALESCE($3, password)
+            WHERE id = $4
+        ",
+        payload.name.value(),
+        payload.email.value(),
+        hashed,
+        user_id
+    );
     let result = query.execute(&pool).await?;
 
     if result.rows_affected() == 0 {
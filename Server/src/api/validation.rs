@@ -0,0 +1,160 @@
+use axum::{
+    Json,
+    extract::{FromRequest, Request},
+};
+use serde::de::DeserializeOwned;
+
+use crate::errors::SorjordetError;
+
+/// Implemented by request payloads that need server-side validation
+/// before they are handed to a handler.
+pub trait Validate {
+    fn validate(&self) -> Result<(), SorjordetError>;
+}
+
+/// Json extractor that runs `Validate::validate` on the payload,
+/// rejecting the request with `InvalidInput` if deserialization or validation fails.
+pub struct ValidatedJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = SorjordetError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(payload) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(|e| SorjordetError::InvalidInput(e.body_text()))?;
+
+        payload.validate()?;
+
+        Ok(ValidatedJson(payload))
+    }
+}
+
+pub fn validate_length(field: &str, value: &str, max_len: usize) -> Result<(), SorjordetError> {
+    if value.chars().count() > max_len {
+        return Err(SorjordetError::InvalidInput(format!(
+            "{field} must be at most {max_len} characters"
+        )));
+    }
+    Ok(())
+}
+
+/// Names must be non-empty and fit in the VARCHAR column they are stored in.
+pub fn validate_name(field: &str, value: &str, max_len: usize) -> Result<(), SorjordetError> {
+    if value.trim().is_empty() {
+        return Err(SorjordetError::InvalidInput(format!(
+            "{field} must not be empty"
+        )));
+    }
+    validate_length(field, value, max_len)
+}
+
+pub fn validate_email(field: &str, value: &str, max_len: usize) -> Result<(), SorjordetError> {
+    validate_name(field, value, max_len)?;
+    match value.split_once('@') {
+        Some((user, domain)) if !user.is_empty() && !domain.is_empty() => Ok(()),
+        _ => Err(SorjordetError::InvalidInput(format!(
+            "{field} must be a valid email address"
+        ))),
+    }
+}
+
+/// Quantities must be finite and not negative.
+pub fn validate_non_negative(field: &str, value: f64) -> Result<(), SorjordetError> {
+    if !value.is_finite() || value < 0.0 {
+        return Err(SorjordetError::InvalidInput(format!(
+            "{field} must be a number, not negative"
+        )));
+    }
+    Ok(())
+}
+
+/// Type of a GeoJSON geometry, bare or as the geometry of a Feature, when it has coordinates.
+fn geojson_geometry_type(value: &str) -> Option<String> {
+    let json = serde_json::from_str::<serde_json::Value>(value).unwrap_or_default();
+    let geometry = match json.get("type").and_then(|x| x.as_str()) {
+        Some("Feature") => json.get("geometry").unwrap_or(&serde_json::Value::Null),
+        _ => &json,
+    };
+    if !geometry.get("coordinates").is_some_and(|x| x.is_array()) {
+        return None;
+    }
+    geometry
+        .get("type")
+        .and_then(|x| x.as_str())
+        .map(str::to_string)
+}
+
+/// Field boundaries are a GeoJSON Polygon or MultiPolygon, or a Feature with one as geometry.
+pub fn validate_geojson(field: &str, value: &str) -> Result<(), SorjordetError> {
+    match geojson_geometry_type(value).as_deref() {
+        Some("Polygon" | "MultiPolygon") => Ok(()),
+        _ => Err(SorjordetError::InvalidInput(format!(
+            "{field} must be a GeoJSON polygon"
+        ))),
+    }
+}
+
+/// A GeoJSON LineString, or a Feature with one as geometry.
+pub fn validate_geojson_line(field: &str, value: &str) -> Result<(), SorjordetError> {
+    match geojson_geometry_type(value).as_deref() {
+        Some("LineString") => Ok(()),
+        _ => Err(SorjordetError::InvalidInput(format!(
+            "{field} must be a GeoJSON line string"
+        ))),
+    }
+}
+
+/// Accepts the colour formats the client draws with:
+/// `#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`, `rgb(r, g, b)` and `rgba(r, g, b, a)`.
+pub fn validate_color(field: &str, value: &str, max_len: usize) -> Result<(), SorjordetError> {
+    validate_length(field, value, max_len)?;
+    if is_hex_color(value) || is_rgb_color(value) {
+        Ok(())
+    } else {
+        Err(SorjordetError::InvalidInput(format!(
+            "{field} must be a hex, rgb() or rgba() colour"
+        )))
+    }
+}
+
+fn is_hex_color(value: &str) -> bool {
+    match value.strip_prefix('#') {
+        Some(hex) => {
+            matches!(hex.len(), 3 | 4 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit())
+        }
+        None => false,
+    }
+}
+
+fn is_rgb_color(value: &str) -> bool {
+    let value = value.trim();
+    let (args, with_alpha) = if let Some(rest) = value.strip_prefix("rgba(") {
+        (rest, true)
+    } else if let Some(rest) = value.strip_prefix("rgb(") {
+        (rest, false)
+    } else {
+        return false;
+    };
+    let Some(args) = args.strip_suffix(')') else {
+        return false;
+    };
+
+    let parts: Vec<&str> = args.split(',').map(str::trim).collect();
+    let expected = if with_alpha { 4 } else { 3 };
+    if parts.len() != expected {
+        return false;
+    }
+
+    let rgb_ok = parts[..3].iter().all(|p| p.parse::<u8>().is_ok());
+    let alpha_ok = !with_alpha
+        || parts[3]
+            .parse::<f64>()
+            .is_ok_and(|a| (0.0..=1.0).contains(&a));
+
+    rgb_ok && alpha_ok
+}
@@ -4,11 +4,14 @@ use axum::{
     response::{IntoResponse, Response},
 };
 
+#[derive(Debug)]
 pub enum SorjordetError {
     AuthError,
     DBError,
     NotFound(String),
     InvalidInput(String),
+    PreconditionFailed(String),
+    Conflict(String),
     InternalError(String),
 }
 
@@ -17,6 +20,8 @@ impl IntoResponse for SorjordetError {
         let (status, error_message) = match self {
             SorjordetError::NotFound(x) => (StatusCode::NOT_FOUND, x),
             SorjordetError::InvalidInput(x) => (StatusCode::UNPROCESSABLE_ENTITY, x),
+            SorjordetError::PreconditionFailed(x) => (StatusCode::PRECONDITION_FAILED, x),
+            SorjordetError::Conflict(x) => (StatusCode::CONFLICT, x),
             SorjordetError::InternalError(x) => (StatusCode::INTERNAL_SERVER_ERROR, x),
             SorjordetError::DBError => (
                 StatusCode::INTERNAL_SERVER_ERROR,
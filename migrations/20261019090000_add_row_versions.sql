@@ -0,0 +1,5 @@
+-- Row versions used for optimistic concurrency (ETag / If-Match)
+ALTER TABLE farm_field ADD COLUMN IF NOT EXISTS version INT NOT NULL DEFAULT 1;
+ALTER TABLE farm_field_group ADD COLUMN IF NOT EXISTS version INT NOT NULL DEFAULT 1;
+ALTER TABLE field_event ADD COLUMN IF NOT EXISTS version INT NOT NULL DEFAULT 1;
+ALTER TABLE harvest_event ADD COLUMN IF NOT EXISTS version INT NOT NULL DEFAULT 1;
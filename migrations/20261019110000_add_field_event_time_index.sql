@@ -0,0 +1,2 @@
+-- Supports keyset pagination of field events ordered by (time, id)
+CREATE INDEX IF NOT EXISTS field_event_time_id_idx ON field_event (time DESC, id DESC);
@@ -0,0 +1,24 @@
+-- Farming seasons start on a configurable day each year, defaulting to calendar years
+ALTER TABLE farm
+    ADD COLUMN IF NOT EXISTS season_start_month INT NOT NULL DEFAULT 1,
+    ADD COLUMN IF NOT EXISTS season_start_day INT NOT NULL DEFAULT 1;
+
+ALTER TABLE farm
+    ADD CONSTRAINT farm_season_start_valid CHECK (
+        season_start_month BETWEEN 1 AND 12
+        AND season_start_day BETWEEN 1 AND 28 + (CASE WHEN season_start_month = 2 THEN 0 ELSE 3 END)
+        AND NOT (season_start_month IN (4, 6, 9, 11) AND season_start_day = 31)
+    );
+
+-- The season a point in time belongs to, named by the calendar year the season starts in
+CREATE OR REPLACE FUNCTION farm_season(t TIMESTAMPTZ, start_month INT, start_day INT)
+RETURNS INT
+LANGUAGE SQL IMMUTABLE
+AS $$
+    SELECT CAST(EXTRACT(year FROM t AT TIME ZONE 'UTC') AS INT)
+        - CASE
+            WHEN (CAST(EXTRACT(month FROM t AT TIME ZONE 'UTC') AS INT), CAST(EXTRACT(day FROM t AT TIME ZONE 'UTC') AS INT))
+                < (start_month, start_day) THEN 1
+            ELSE 0
+        END
+$$;
@@ -0,0 +1,30 @@
+-- Harvest types get a unit of measure and optional factors for converting to mass,
+-- and harvest values can be fractional
+ALTER TABLE harvest_type
+    ADD COLUMN IF NOT EXISTS unit VARCHAR(32) NOT NULL DEFAULT 'bale'
+        CHECK (unit IN ('bale', 'kg', 'tonne', 'litre', 'cubic_metre')),
+    ADD COLUMN IF NOT EXISTS kg_per_unit DOUBLE PRECISION CHECK (kg_per_unit > 0),
+    ADD COLUMN IF NOT EXISTS dry_matter_pct DOUBLE PRECISION CHECK (dry_matter_pct BETWEEN 0 AND 100);
+
+ALTER TABLE harvest_event ALTER COLUMN value TYPE DOUBLE PRECISION;
+
+-- Factor converting a harvest value to the target unit ('native', 'kg' or 'tonne'),
+-- optionally as dry matter. NULL when the harvest type lacks the factors needed.
+CREATE OR REPLACE FUNCTION harvest_unit_factor(
+    unit VARCHAR,
+    kg_per_unit DOUBLE PRECISION,
+    dry_matter_pct DOUBLE PRECISION,
+    target TEXT,
+    dry_matter BOOLEAN
+)
+RETURNS DOUBLE PRECISION
+LANGUAGE SQL IMMUTABLE
+AS $$
+    SELECT
+        (CASE target
+            WHEN 'native' THEN 1
+            ELSE (CASE unit WHEN 'kg' THEN 1 WHEN 'tonne' THEN 1000 ELSE kg_per_unit END)
+                / (CASE target WHEN 'tonne' THEN 1000 ELSE 1 END)
+        END)
+        * (CASE WHEN dry_matter THEN dry_matter_pct / 100 ELSE 1 END)
+$$;
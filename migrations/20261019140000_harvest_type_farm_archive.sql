@@ -0,0 +1,39 @@
+-- Harvest types belong to a farm, can be archived and have unique names per farm
+ALTER TABLE harvest_type
+    ADD COLUMN IF NOT EXISTS farm_id INT REFERENCES farm(id),
+    ADD COLUMN IF NOT EXISTS archived BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- the farm with the most harvests of the type, or the first farm for unused types
+UPDATE harvest_type AS t
+SET farm_id = COALESCE(
+    (SELECT f.farm_id
+        FROM harvest_event e JOIN farm_field f ON f.id = e.field_id
+        WHERE e.harvest_type_id = t.id
+        GROUP BY f.farm_id
+        ORDER BY COUNT(*) DESC, f.farm_id
+        LIMIT 1),
+    (SELECT MIN(id) FROM farm))
+WHERE farm_id IS NULL;
+
+-- without any farm there are no fields either, so the types left are unused
+DELETE FROM harvest_type WHERE farm_id IS NULL;
+
+ALTER TABLE harvest_type ALTER COLUMN farm_id SET NOT NULL;
+
+-- merge types with the same name on a farm into the oldest one, ignoring case
+-- and surrounding whitespace
+UPDATE harvest_type SET name = TRIM(name) WHERE name <> TRIM(name);
+UPDATE harvest_event AS e
+SET harvest_type_id = d.canonical_id,
+    version = version + 1,
+    updated_at = NOW()
+FROM (SELECT id, MIN(id) OVER (PARTITION BY farm_id, LOWER(name)) AS canonical_id
+        FROM harvest_type) AS d
+WHERE e.harvest_type_id = d.id AND d.id <> d.canonical_id;
+
+DELETE FROM harvest_type AS t
+USING (SELECT id, MIN(id) OVER (PARTITION BY farm_id, LOWER(name)) AS canonical_id
+        FROM harvest_type) AS d
+WHERE t.id = d.id AND d.id <> d.canonical_id;
+
+CREATE UNIQUE INDEX IF NOT EXISTS harvest_type_farm_name_key ON harvest_type (farm_id, LOWER(name));
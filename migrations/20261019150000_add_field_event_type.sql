@@ -0,0 +1,37 @@
+-- Catalogue of field event types, replacing the free text event name
+CREATE TABLE IF NOT EXISTS field_event_type (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(128) NOT NULL,
+    farm_id INT NOT NULL REFERENCES farm(id),
+    archived BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_by VARCHAR(256),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_by VARCHAR(256)
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS field_event_type_farm_name_key
+    ON field_event_type (farm_id, LOWER(name));
+
+-- one type per farm and name ignoring case and surrounding whitespace,
+-- named by the most used spelling
+INSERT INTO field_event_type (name, farm_id)
+SELECT DISTINCT ON (f.farm_id, LOWER(TRIM(e.event_name))) TRIM(e.event_name), f.farm_id
+FROM field_event e JOIN farm_field f ON f.id = e.field_id
+GROUP BY f.farm_id, e.event_name
+ORDER BY f.farm_id, LOWER(TRIM(e.event_name)), COUNT(*) DESC, TRIM(e.event_name)
+ON CONFLICT DO NOTHING;
+
+ALTER TABLE field_event ADD COLUMN IF NOT EXISTS type_id INT REFERENCES field_event_type(id);
+
+UPDATE field_event AS e
+SET type_id = t.id
+FROM farm_field f, field_event_type t
+WHERE f.id = e.field_id
+    AND t.farm_id = f.farm_id
+    AND LOWER(t.name) = LOWER(TRIM(e.event_name));
+
+ALTER TABLE field_event ALTER COLUMN type_id SET NOT NULL;
+ALTER TABLE field_event DROP COLUMN event_name;
+
+CREATE INDEX IF NOT EXISTS field_event_type_id_idx ON field_event (type_id);
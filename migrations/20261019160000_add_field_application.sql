@@ -0,0 +1,14 @@
+-- Structured fertilizer, manure and pesticide applications, one per field event
+CREATE TABLE IF NOT EXISTS field_application (
+    field_event_id INT PRIMARY KEY REFERENCES field_event(id) ON DELETE CASCADE,
+    kind VARCHAR(32) NOT NULL CHECK (kind IN ('fertilizer', 'manure', 'pesticide', 'lime', 'other')),
+    product VARCHAR(256) NOT NULL,
+    amount DOUBLE PRECISION NOT NULL CHECK (amount >= 0),
+    unit VARCHAR(32) NOT NULL CHECK (unit IN ('kg', 'tonne', 'litre', 'cubic_metre')),
+    n_kg_per_unit DOUBLE PRECISION CHECK (n_kg_per_unit >= 0),
+    p_kg_per_unit DOUBLE PRECISION CHECK (p_kg_per_unit >= 0),
+    k_kg_per_unit DOUBLE PRECISION CHECK (k_kg_per_unit >= 0),
+    area_ha DOUBLE PRECISION CHECK (area_ha > 0),
+    operator VARCHAR(256),
+    weather VARCHAR(512)
+);
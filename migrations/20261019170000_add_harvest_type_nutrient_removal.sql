@@ -0,0 +1,5 @@
+-- Kilograms of N, P and K removed from the field per unit harvested
+ALTER TABLE harvest_type
+    ADD COLUMN IF NOT EXISTS n_removal_kg_per_unit DOUBLE PRECISION CHECK (n_removal_kg_per_unit >= 0),
+    ADD COLUMN IF NOT EXISTS p_removal_kg_per_unit DOUBLE PRECISION CHECK (p_removal_kg_per_unit >= 0),
+    ADD COLUMN IF NOT EXISTS k_removal_kg_per_unit DOUBLE PRECISION CHECK (k_removal_kg_per_unit >= 0);
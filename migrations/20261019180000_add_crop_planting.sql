@@ -0,0 +1,21 @@
+-- What is planted on a field, and when
+CREATE TABLE IF NOT EXISTS crop_planting (
+    id SERIAL PRIMARY KEY,
+    field_id INT NOT NULL REFERENCES farm_field(id) ON DELETE CASCADE,
+    crop VARCHAR(128) NOT NULL,
+    variety VARCHAR(128),
+    sow_date DATE NOT NULL,
+    expected_harvest_date DATE,
+    terminate_date DATE,
+    version INT NOT NULL DEFAULT 1,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_by VARCHAR(256),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_by VARCHAR(256),
+    CONSTRAINT crop_planting_dates_ordered CHECK (
+        (expected_harvest_date IS NULL OR expected_harvest_date >= sow_date)
+        AND (terminate_date IS NULL OR terminate_date >= sow_date)
+    )
+);
+
+CREATE INDEX IF NOT EXISTS crop_planting_field_sow_date_idx ON crop_planting (field_id, sow_date);
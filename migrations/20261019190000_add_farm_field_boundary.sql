@@ -0,0 +1,71 @@
+-- Field boundaries as PostGIS geometry instead of GeoJSON text
+CREATE EXTENSION IF NOT EXISTS postgis;
+
+-- The client sends a GeoJSON Feature, or a bare Polygon or MultiPolygon,
+-- in the map's web mercator coordinates (EPSG:3857). Stored in WGS84.
+CREATE OR REPLACE FUNCTION field_boundary_from_geojson(geojson TEXT)
+RETURNS geometry
+LANGUAGE SQL IMMUTABLE STRICT
+AS $$
+    SELECT ST_Multi(ST_CollectionExtract(ST_MakeValid(ST_Transform(ST_SetSRID(
+        ST_GeomFromGeoJSON(CASE WHEN j->>'type' = 'Feature' THEN j->'geometry' ELSE j END),
+        3857), 4326)), 3))
+    FROM (SELECT geojson::jsonb AS j) AS x
+$$;
+
+-- GeoJSON Feature in EPSG:3857 as the client draws it, a Polygon unless the field has several parts
+CREATE OR REPLACE FUNCTION field_boundary_geojson(boundary geometry)
+RETURNS TEXT
+LANGUAGE SQL IMMUTABLE STRICT
+AS $$
+    SELECT json_build_object(
+        'type', 'Feature',
+        'geometry', ST_AsGeoJSON(ST_Transform(
+            CASE WHEN ST_NumGeometries(boundary) = 1 THEN ST_GeometryN(boundary, 1) ELSE boundary END,
+            3857))::json,
+        'properties', NULL
+    )::text
+$$;
+
+ALTER TABLE farm_field ADD COLUMN IF NOT EXISTS boundary geometry(MultiPolygon, 4326);
+
+-- Strings that aren't a polygon are kept here instead of being lost with the old column,
+-- those fields are left without a boundary until one is drawn or imported
+CREATE TABLE IF NOT EXISTS farm_field_unconverted_boundary (
+    field_id INT PRIMARY KEY REFERENCES farm_field(id) ON DELETE CASCADE,
+    map_polygon_string VARCHAR NOT NULL,
+    error TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+DO $$
+DECLARE
+    f RECORD;
+BEGIN
+    FOR f IN SELECT id, map_polygon_string FROM farm_field
+            WHERE boundary IS NULL AND TRIM(map_polygon_string) <> '' LOOP
+        BEGIN
+            UPDATE farm_field
+            SET boundary = field_boundary_from_geojson(f.map_polygon_string)
+            WHERE id = f.id;
+        EXCEPTION WHEN OTHERS THEN
+            RAISE NOTICE 'farm_field % has no valid polygon: %', f.id, SQLERRM;
+            INSERT INTO farm_field_unconverted_boundary (field_id, map_polygon_string, error)
+            VALUES (f.id, f.map_polygon_string, SQLERRM);
+        END;
+    END LOOP;
+END $$;
+
+INSERT INTO farm_field_unconverted_boundary (field_id, map_polygon_string, error)
+SELECT id, map_polygon_string, 'no polygon area'
+FROM farm_field
+WHERE ST_IsEmpty(boundary);
+
+UPDATE farm_field SET boundary = NULL WHERE ST_IsEmpty(boundary);
+
+ALTER TABLE farm_field
+    ADD CONSTRAINT farm_field_boundary_not_empty CHECK (NOT ST_IsEmpty(boundary));
+
+CREATE INDEX IF NOT EXISTS farm_field_boundary_idx ON farm_field USING GIST (boundary);
+
+ALTER TABLE farm_field DROP COLUMN map_polygon_string;
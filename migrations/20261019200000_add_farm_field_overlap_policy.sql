@@ -0,0 +1,4 @@
+-- Whether fields drawn overlapping other fields of the farm are rejected or saved with a warning
+ALTER TABLE farm
+    ADD COLUMN IF NOT EXISTS field_overlap_policy VARCHAR(16) NOT NULL DEFAULT 'warn'
+        CHECK (field_overlap_policy IN ('reject', 'warn'));
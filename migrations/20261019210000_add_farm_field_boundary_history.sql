@@ -0,0 +1,69 @@
+-- Every boundary a field has had, so statistics can use the one valid at the time
+CREATE TABLE IF NOT EXISTS farm_field_boundary (
+    id SERIAL PRIMARY KEY,
+    field_id INT NOT NULL REFERENCES farm_field(id) ON DELETE CASCADE,
+    boundary geometry(MultiPolygon, 4326) NOT NULL,
+    valid_from TIMESTAMPTZ NOT NULL,
+    -- NULL for the current boundary
+    valid_to TIMESTAMPTZ,
+    -- version of the field the boundary was drawn in
+    field_version INT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_by VARCHAR(256),
+    CONSTRAINT farm_field_boundary_valid_ordered CHECK (valid_to IS NULL OR valid_to >= valid_from)
+);
+
+CREATE INDEX IF NOT EXISTS farm_field_boundary_field_valid_from_idx
+    ON farm_field_boundary (field_id, valid_from);
+
+-- existing boundaries have been valid since the field was created
+INSERT INTO farm_field_boundary (field_id, boundary, valid_from, field_version, created_by)
+SELECT id, boundary, created_at, version, updated_by
+FROM farm_field
+WHERE boundary IS NOT NULL
+    AND NOT EXISTS (SELECT 1 FROM farm_field_boundary b WHERE b.field_id = farm_field.id);
+
+CREATE OR REPLACE FUNCTION farm_field_boundary_version()
+RETURNS TRIGGER
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    UPDATE farm_field_boundary
+    SET valid_to = NOW()
+    WHERE field_id = NEW.id AND valid_to IS NULL;
+
+    IF NEW.boundary IS NOT NULL THEN
+        INSERT INTO farm_field_boundary (field_id, boundary, valid_from, field_version, created_by)
+        VALUES (NEW.id, NEW.boundary, NOW(), NEW.version, NEW.updated_by);
+    END IF;
+
+    RETURN NULL;
+END $$;
+
+DROP TRIGGER IF EXISTS farm_field_boundary_insert ON farm_field;
+CREATE TRIGGER farm_field_boundary_insert
+    AFTER INSERT ON farm_field
+    FOR EACH ROW
+    EXECUTE FUNCTION farm_field_boundary_version();
+
+DROP TRIGGER IF EXISTS farm_field_boundary_update ON farm_field;
+CREATE TRIGGER farm_field_boundary_update
+    AFTER UPDATE OF boundary ON farm_field
+    FOR EACH ROW
+    WHEN (OLD.boundary IS DISTINCT FROM NEW.boundary)
+    EXECUTE FUNCTION farm_field_boundary_version();
+
+-- Boundary of a field at a point in time. Times before the first
+-- boundary was drawn use the first one.
+CREATE OR REPLACE FUNCTION field_boundary_at(field INT, at TIMESTAMPTZ)
+RETURNS geometry
+LANGUAGE SQL STABLE STRICT
+AS $$
+    SELECT boundary
+    FROM farm_field_boundary
+    WHERE field_id = field
+    ORDER BY valid_from <= at DESC,
+        CASE WHEN valid_from <= at THEN valid_from END DESC,
+        valid_from
+    LIMIT 1
+$$;
@@ -0,0 +1,54 @@
+-- Fields replaced by a split or merge are retired, keeping their events and harvests
+ALTER TABLE farm_field ADD COLUMN IF NOT EXISTS retired_at TIMESTAMPTZ;
+ALTER TABLE farm_field ADD COLUMN IF NOT EXISTS retired_by VARCHAR(256);
+
+-- Which fields a field was split from or merged from
+CREATE TABLE IF NOT EXISTS farm_field_lineage (
+    parent_field_id INT NOT NULL REFERENCES farm_field(id) ON DELETE CASCADE,
+    child_field_id INT NOT NULL REFERENCES farm_field(id) ON DELETE CASCADE,
+    operation VARCHAR(16) NOT NULL CHECK (operation IN ('split', 'merge')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_by VARCHAR(256),
+    PRIMARY KEY (parent_field_id, child_field_id)
+);
+
+CREATE INDEX IF NOT EXISTS farm_field_lineage_child_idx ON farm_field_lineage (child_field_id);
+
+-- Parts of a boundary cut by a LineString, bare or in a Feature, in the map's
+-- EPSG:3857 coordinates, numbered from west to east
+CREATE OR REPLACE FUNCTION field_boundary_split(boundary geometry, line_geojson TEXT)
+RETURNS TABLE (part BIGINT, geom geometry)
+LANGUAGE SQL IMMUTABLE STRICT
+AS $$
+    SELECT ROW_NUMBER() OVER (
+            ORDER BY ST_X(ST_Centroid(d.geom)), ST_Y(ST_Centroid(d.geom))
+        ), ST_Multi(d.geom)
+    FROM (SELECT line_geojson::jsonb AS j) AS x,
+        ST_Dump(ST_Split(
+            boundary,
+            ST_Transform(ST_SetSRID(ST_GeomFromGeoJSON(
+                CASE WHEN j->>'type' = 'Feature' THEN j->'geometry' ELSE j END), 3857), 4326)
+        )) AS d
+    WHERE ST_Area(d.geom::geography) > 0
+$$;
+
+-- A field and every field it was split or merged from
+CREATE OR REPLACE FUNCTION field_with_predecessors(field INT)
+RETURNS TABLE (field_id INT)
+LANGUAGE SQL STABLE STRICT
+AS $$
+    WITH RECURSIVE lineage(id) AS (
+        SELECT field
+        UNION
+        SELECT l.parent_field_id
+        FROM farm_field_lineage l
+            JOIN lineage ON lineage.id = l.child_field_id
+    )
+    SELECT id FROM lineage
+$$;
+
+-- Retired fields keep their names, so only active fields need unique ones,
+-- letting a successor take the name of the field it replaces
+ALTER TABLE farm_field DROP CONSTRAINT IF EXISTS farm_field_name_key;
+CREATE UNIQUE INDEX IF NOT EXISTS farm_field_active_name_key
+    ON farm_field (name) WHERE retired_at IS NULL;
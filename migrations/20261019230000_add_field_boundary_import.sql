@@ -0,0 +1,12 @@
+-- Boundary from an imported GeoJSON geometry in `source_crs`, the WKT of a
+-- Shapefile's .prj or any other definition PROJ understands, WGS84 when NULL
+CREATE OR REPLACE FUNCTION field_boundary_import(geojson TEXT, source_crs TEXT)
+RETURNS geometry
+LANGUAGE SQL IMMUTABLE
+AS $$
+    SELECT ST_Multi(ST_CollectionExtract(ST_MakeValid(
+        CASE WHEN source_crs IS NULL THEN ST_SetSRID(g, 4326)
+            ELSE ST_Transform(g, source_crs, 4326) END
+    ), 3))
+    FROM (SELECT ST_GeomFromGeoJSON(geojson) AS g) AS x
+$$;
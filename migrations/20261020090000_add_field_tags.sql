@@ -0,0 +1,24 @@
+-- Labels for grouping fields several ways at once, unlike the single farm_field_group
+CREATE TABLE IF NOT EXISTS field_tag (
+    id SERIAL PRIMARY KEY,
+    farm_id INT NOT NULL REFERENCES farm(id),
+    name VARCHAR(128) NOT NULL,
+    -- what the tag groups by, like soil type or ownership
+    category VARCHAR(128),
+    draw_color VARCHAR(64),
+    version INT NOT NULL DEFAULT 1,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_by VARCHAR(256),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_by VARCHAR(256)
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS field_tag_farm_name_idx ON field_tag (farm_id, LOWER(name));
+
+CREATE TABLE IF NOT EXISTS farm_field_tag (
+    field_id INT NOT NULL REFERENCES farm_field(id) ON DELETE CASCADE,
+    tag_id INT NOT NULL REFERENCES field_tag(id) ON DELETE CASCADE,
+    PRIMARY KEY (field_id, tag_id)
+);
+
+CREATE INDEX IF NOT EXISTS farm_field_tag_tag_idx ON farm_field_tag (tag_id);